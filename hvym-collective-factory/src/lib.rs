@@ -8,6 +8,7 @@ use soroban_sdk::{contract, contractimpl, symbol_short, Address, BytesN, Bytes,
 pub struct CollectiveFactory;
 
 const ADMIN: Symbol = symbol_short!("admin");
+const PENDING: Symbol = symbol_short!("pending");
 
 mod hvym_collective {
     soroban_sdk::contractimport!(
@@ -22,7 +23,48 @@ impl CollectiveFactory {
         env.storage().instance().set(&ADMIN, &admin);
     }
 
-    pub fn deploy(env: Env, join_fee: u32, pay_token: Address, mint_fee: u32, reward: u32) -> Address {
+    /// Proposes a new administrator, who takes over once they accept.
+    pub fn propose_admin(env: Env, new_admin: Address) {
+        let admin: Address = env.storage().instance().get(&ADMIN).unwrap();
+        admin.require_auth();
+
+        env.storage().instance().set(&PENDING, &new_admin);
+        env.events().publish((ADMIN, symbol_short!("propose")), (admin, new_admin));
+    }
+
+    /// Completes the handover. This has to be authorized by the proposed
+    /// administrator.
+    pub fn accept_admin(env: Env) {
+        let new_admin: Address = env.storage().instance().get(&PENDING).expect("no pending admin");
+        new_admin.require_auth();
+
+        let admin: Address = env.storage().instance().get(&ADMIN).unwrap();
+        env.storage().instance().set(&ADMIN, &new_admin);
+        env.storage().instance().remove(&PENDING);
+        env.events().publish((ADMIN, symbol_short!("accept")), (admin, new_admin));
+    }
+
+    pub fn cancel_admin_proposal(env: Env) {
+        let admin: Address = env.storage().instance().get(&ADMIN).unwrap();
+        admin.require_auth();
+
+        let pending: Address = env.storage().instance().get(&PENDING).expect("no pending admin");
+        env.storage().instance().remove(&PENDING);
+        env.events().publish((ADMIN, symbol_short!("cancel")), (admin, pending));
+    }
+
+    pub fn admin(env: Env) -> Address {
+        env.storage().instance().get(&ADMIN).unwrap()
+    }
+
+    pub fn pending_admin(env: Env) -> Option<Address> {
+        env.storage().instance().get(&PENDING)
+    }
+
+    /// Deploys a collective administered by the factory admin. Council mode
+    /// is a property of each collective, switched on with its `set_council`;
+    /// the factory holds no funds and keeps a single admin.
+    pub fn deploy(env: Env, join_fee: i128, pay_token: Address, mint_fee: i128, reward: i128) -> Address {
         let admin: Address = env.storage().instance().get(&ADMIN).unwrap();
         admin.require_auth();
 
@@ -24,15 +24,17 @@ fn test() {
     let admin = Address::generate(&env);
     let deployer_client = CollectiveFactoryClient::new(&env, &env.register(CollectiveFactory, (&admin,)));
     let symbol: Symbol = symbol_short!("HVYM");
-    let num1: u32 = 30_u32;
-    let num2: u32 = 30_u32;
-    let num3: u32 = 30_u32;
+    let num1: i128 = 30;
+    let num2: i128 = 30;
+    let num3: i128 = 30;
     let str_addr = String::from_str(&env, &"CDLZFC3SYJYDZT7K67VZ75HPJVIEUVNIXF47ZG2FB2RMQQVU2HHGCYSC");
     let xlm: Address = Address::from_string(&str_addr);
     //let salt = BytesN::from_array(&env, &[0; 32]);
 
     // Deploy contract using deployer, and include an init function to call.
     env.mock_all_auths();
+    // uploading an unoptimized collective wasm outgrows the default budget
+    env.cost_estimate().budget().reset_unlimited();
     let contract_id = deployer_client.deploy(&num1, &xlm, &num2, &num3);
 
     // An authorization from the admin is required.
@@ -54,3 +56,33 @@ fn test() {
 
 
 }
+
+#[test]
+fn test_admin_transfer() {
+    let env = Env::default();
+    let admin = Address::generate(&env);
+    let typo = Address::generate(&env);
+    let new_admin = Address::generate(&env);
+    let deployer_client = CollectiveFactoryClient::new(&env, &env.register(CollectiveFactory, (&admin,)));
+    env.mock_all_auths();
+
+    deployer_client.propose_admin(&typo);
+    deployer_client.cancel_admin_proposal();
+    assert_eq!(deployer_client.pending_admin(), None);
+
+    deployer_client.propose_admin(&new_admin);
+    assert_eq!(deployer_client.admin(), admin);
+
+    deployer_client.accept_admin();
+    let expected_auth = AuthorizedInvocation {
+        function: AuthorizedFunction::Contract((
+            deployer_client.address.clone(),
+            Symbol::new(&env, "accept_admin"),
+            ().into_val(&env),
+        )),
+        sub_invocations: vec![],
+    };
+    assert_eq!(env.auths(), vec![(new_admin.clone(), expected_auth)]);
+    assert_eq!(deployer_client.admin(), new_admin);
+    assert_eq!(deployer_client.pending_admin(), None);
+}
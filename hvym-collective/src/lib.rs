@@ -2,7 +2,8 @@
 
 use soroban_sdk::{
     contract, contractimpl, contracttype, symbol_short, Address, Env, IntoVal, 
-    TryFromVal, Val, Vec, Error, Symbol, String, BytesN, FromVal, Bytes, token
+    TryFromVal, Val, Vec, Error, Symbol, String, BytesN, FromVal, Bytes, token,
+    xdr::ToXdr
 };
 
 const ADMIN: Symbol = symbol_short!("admin");
@@ -12,6 +13,67 @@ const OPUS: Symbol = symbol_short!("OPUS");
 const JOIN: Symbol = symbol_short!("JOIN");
 const REMOVE: Symbol = symbol_short!("REMOVE");
 const PUBLISH: Symbol = symbol_short!("PUBLISH");
+const TIER: Symbol = symbol_short!("TIER");
+const RENEW: Symbol = symbol_short!("RENEW");
+const LEAVE: Symbol = symbol_short!("LEAVE");
+const REFER: Symbol = symbol_short!("REFER");
+const PROFILE: Symbol = symbol_short!("PROFILE");
+const BAN: Symbol = symbol_short!("BAN");
+const ROLE: Symbol = symbol_short!("ROLE");
+const SPONSOR: Symbol = symbol_short!("SPONSOR");
+const APPLY: Symbol = symbol_short!("APPLY");
+const SUSPEND: Symbol = symbol_short!("SUSPEND");
+const VOUCHER: Symbol = symbol_short!("VOUCHER");
+const PAYOUT: Symbol = symbol_short!("PAYOUT");
+const COUNCIL: Symbol = symbol_short!("COUNCIL");
+const ADMIN_EVENT: Symbol = symbol_short!("ADMIN");
+const FEES: Symbol = symbol_short!("FEES");
+const DIVIDEND: Symbol = symbol_short!("DIVIDEND");
+const VESTING: Symbol = symbol_short!("VESTING");
+
+const MAX_BPS: u32 = 10_000;
+// Upper bound on any fee, reward or allocation, in the token's smallest unit.
+const MAX_AMOUNT: i128 = 1_000_000_000_000_000_000;
+// Ledgers of notice members get before a price change, about a week.
+pub const FEE_NOTICE: u32 = 120_960;
+// Ledgers holders have to claim a dividend before the rest can be reclaimed,
+// about three weeks. OPUS balance checkpoints live for 30 days after they are
+// last touched, so the window has to close well before they are archived.
+pub const DIVIDEND_WINDOW: u32 = 362_880;
+
+const JOIN_FEE: Symbol = symbol_short!("join");
+const MINT_FEE: Symbol = symbol_short!("mint");
+
+// Kinds of token listed on a member's profile.
+const NODE_TOKEN: Symbol = symbol_short!("node");
+const FILE_TOKEN: Symbol = symbol_short!("file");
+
+// Treasury ledger categories.
+pub const INCOME_JOIN: Symbol = symbol_short!("join");
+pub const INCOME_RENEW: Symbol = symbol_short!("renew");
+pub const INCOME_MINT: Symbol = symbol_short!("mint");
+pub const INCOME_PUBLISH: Symbol = symbol_short!("publish");
+pub const INCOME_SHARE: Symbol = symbol_short!("share");
+pub const INCOME_DONATION: Symbol = symbol_short!("donation");
+pub const OUTFLOW_WITHDRAW: Symbol = symbol_short!("withdraw");
+pub const OUTFLOW_REFUND: Symbol = symbol_short!("refund");
+pub const OUTFLOW_DIVIDEND: Symbol = symbol_short!("dividend");
+
+pub const TREASURER: Symbol = symbol_short!("treasurer");
+pub const MODERATOR: Symbol = symbol_short!("moderator");
+pub const FEE_MANAGER: Symbol = symbol_short!("fees");
+pub const LAUNCHER: Symbol = symbol_short!("launcher");
+pub const VOUCHER_ISSUER: Symbol = symbol_short!("vouchers");
+
+const DEFAULT_TIER: Symbol = symbol_short!("member");
+
+// Tier privileges, combined as a bit mask in `Tier::actions`.
+pub const ACTION_NODE: u32 = 1;
+pub const ACTION_FILE: u32 = 2;
+pub const ACTION_PUBLISH: u32 = 4;
+pub const ALL_ACTIONS: u32 = ACTION_NODE | ACTION_FILE | ACTION_PUBLISH;
+
+const MAX_PAGE: u32 = 100;
 
 mod pintheon_node_token {
     soroban_sdk::contractimport!(
@@ -31,24 +93,390 @@ mod opus_token {
     );
 }
 
+mod opus_staking {
+    soroban_sdk::contractimport!(
+        file = "../opus_staking/target/wasm32-unknown-unknown/release/opus_staking.optimized.wasm"
+    );
+}
+
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum Datakey {
     Member(Address),
     Collective,
     Admin,
+    Tier(Symbol),
+    Tiers,
+    Term,
+    RefundPolicy,
+    ReferralPolicy,
+    Referrer(Address),
+    Referrals(Address),
+    MemberCount,
+    MemberAt(u32),
+    MemberIndex(Address),
+    Profile(Address),
+    ProfileToken(Address, Symbol, u32),
+    ProfileTokenCount(Address, Symbol),
+    Ban(Address),
+    Role(Symbol, Address),
+    Pool(u32),
+    PoolCount,
+    Reserved,
+    JoinMode,
+    Application(Address),
+    Suspension(Address),
+    VoucherKey(BytesN<32>),
+    VoucherNonce(BytesN<32>, u64),
+    MintCredit(Address),
+    Income(Symbol),
+    IncomeFrom(Address),
+    Outflow(Symbol),
+    Withdrawn(Address),
+    Splits,
+    Council,
+    Proposal(u32),
+    ProposalCount,
+    PendingAdmin,
+    FeeSchedule,
+    TierSchedule(Symbol),
+    TermSchedule,
+    RefundSchedule,
+    DisputeWindow,
+    Escrow(u32),
+    EscrowCount,
+    Dividend(u32),
+    DividendCount,
+    DividendClaim(u32, Address),
+    Staking,
+    Grant(u32),
+    GrantCount,
+    Granted,
 }
 
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct Member {
     pub address: Address,
-    pub paid: u32,
+    pub paid: i128,
+    pub tier: Symbol,
+    pub joined: u64,
+    pub joined_ledger: u32,
+    pub expires: u64,
+}
+
+/// Membership duration and renewal terms, in seconds. A `duration` of 0 means
+/// memberships never expire.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MembershipTerm {
+    pub duration: u64,
+    pub grace: u64,
+    pub renew_fee: i128,
+}
+
+/// Share of the paid join fee returned to a member who leaves. The refund
+/// decays linearly to zero over `window` seconds of membership; a `window`
+/// of 0 refunds `percent` regardless of age.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RefundPolicy {
+    pub percent: u32,
+    pub window: u64,
+}
+
+/// OPUS paid to a member for each newcomer they refer. At most `cap` bonuses
+/// are paid per referrer in every `period` seconds; a `cap` of 0 is unlimited.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ReferralPolicy {
+    pub bonus: i128,
+    pub cap: u32,
+    pub period: u64,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ReferralStats {
+    pub count: u32,
+    pub period_start: u64,
+    pub period_count: u32,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum JoinMode {
+    Open,
+    Gated,
+}
+
+/// A pending membership application. `portfolio` is an IPFS CID and `escrow`
+/// is the join fee held until a moderator decides.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Application {
+    pub tier: Symbol,
+    pub portfolio: String,
+    pub escrow: i128,
+    pub submitted: u64,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum MemberStatus {
+    NotMember,
+    Active,
+    Grace,
+    Expired,
+}
+
+/// Public profile of a member. `avatar` and `bio` are IPFS CIDs. The
+/// contracts a member deployed through the collective are listed separately
+/// by `node_tokens` and `file_tokens`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Profile {
+    pub handle: String,
+    pub avatar: String,
+    pub bio: String,
+    pub joined: u64,
+}
+
+/// A ban on an address. `reason` and `appeal` are IPFS CIDs; an `expires`
+/// timestamp of 0 means the ban is permanent.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Ban {
+    pub reason: String,
+    pub banned_at: u64,
+    pub expires: u64,
+    pub appeal: Option<String>,
+}
+
+/// Funds set aside by a sponsor to cover join fees for newcomers approved by
+/// a moderator. Pool balances are held by the collective but excluded from
+/// withdrawals.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SponsorPool {
+    pub funder: Address,
+    pub balance: i128,
+}
+
+/// A temporary suspension. The member keeps their record but is not treated
+/// as a member until ledger `until`. `reason` is an IPFS CID.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Suspension {
+    pub since: u32,
+    pub until: u32,
+    pub reason: String,
+}
+
+/// An off-chain fee waiver, signed with a registered ed25519 voucher key over
+/// its XDR encoding. `fee` is either `join` or `mint`; a mint voucher is held
+/// as a credit against the member's next mint fee.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Voucher {
+    pub collective: Address,
+    pub recipient: Address,
+    pub fee: Symbol,
+    pub discount: u32,
+    pub expires: u64,
+    pub nonce: u64,
+}
+
+/// Cumulative treasury totals since the ledger was introduced.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TreasuryReport {
+    pub joins: i128,
+    pub renewals: i128,
+    pub mints: i128,
+    pub publishes: i128,
+    pub shares: i128,
+    pub donations: i128,
+    pub withdrawals: i128,
+    pub refunds: i128,
+    pub dividends: i128,
+    pub reserved: i128,
+    pub balance: i128,
+}
+
+/// One recipient's share of distributed revenue, in basis points.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Split {
+    pub recipient: Address,
+    pub bps: u32,
+}
+
+/// Signers that must approve privileged actions once council mode is on.
+/// Proposals expire `window` ledgers after they are made.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Council {
+    pub signers: Vec<Address>,
+    pub threshold: u32,
+    pub window: u32,
+}
+
+/// Privileged actions that go through the council when one is set.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum CouncilAction {
+    Withdraw(Address),
+    WithdrawAmount(Address, i128),
+    SetSplits(Vec<Split>),
+    Distribute,
+    UpdateJoinFee(i128),
+    UpdateMintFee(i128),
+    UpdateOpusReward(i128),
+    Remove(Address),
+    LaunchOpus(i128, i128, u32, i128),
+    SetCouncil(Vec<Address>, u32, u32),
+    ScheduleFees(FeeSchedule),
+    DeclareDividend(i128),
+    Ban(Address, String, u64),
+    SetTier(Tier),
+    SetTerm(MembershipTerm),
+    ProposeAdmin(Address),
+    GrantVesting(Address, i128, u64, u64, bool),
+    RevokeGrant(u32),
+    CancelAdminProposal,
+    GrantRole(Symbol, Address),
+    RevokeRole(Symbol, Address),
+    SetJoinMode(JoinMode),
+    AddVoucherKey(BytesN<32>),
+    SetReferralPolicy(ReferralPolicy),
+    RemoveTier(Symbol),
+    SetDisputeWindow(u64),
+    SetRefundPolicy(RefundPolicy),
+    SetStaking(Address),
+}
+
+/// A mint fee held back from the treasury until its dispute window ends.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Escrow {
+    pub payer: Address,
+    pub amount: i128,
+    pub reward: i128,
+    pub category: Symbol,
+    pub ipfs_hash: String,
+    pub release_at: u64,
+}
+
+/// OPUS from the initial allocation vesting to `beneficiary`. Nothing is
+/// released before `cliff`; after that the grant vests linearly from `start`
+/// until `end`. A revoked grant keeps only what had vested when it was
+/// revoked.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Grant {
+    pub beneficiary: Address,
+    pub amount: i128,
+    pub released: i128,
+    pub start: u64,
+    pub cliff: u64,
+    pub end: u64,
+    pub revocable: bool,
+    pub revoked: bool,
+}
+
+/// Pay token earmarked for OPUS holders, shared pro rata by their balances at
+/// the close of ledger `snapshot`, counting OPUS they had staked. `supply`
+/// excludes OPUS held by the collective itself. Stakes are read from the
+/// `staking` contract set when the dividend was declared. Whatever is
+/// unclaimed after ledger `deadline` can be reclaimed into the treasury.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Dividend {
+    pub amount: i128,
+    pub snapshot: u32,
+    pub supply: i128,
+    pub claimed: i128,
+    pub deadline: u32,
+    pub reclaimed: i128,
+    pub staking: Option<Address>,
+}
+
+/// Fees queued to replace the collective's current ones from ledger
+/// `effective` onwards.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FeeSchedule {
+    pub join_fee: i128,
+    pub mint_fee: i128,
+    pub opus_reward: i128,
+    pub effective: u32,
+}
+
+/// A change to an existing tier that takes over from ledger `effective`.
+/// When `removed` is set the tier goes away instead, moving its members to
+/// the default tier.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TierSchedule {
+    pub tier: Tier,
+    pub removed: bool,
+    pub effective: u32,
+}
+
+/// A new refund policy that takes over from ledger `effective`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RefundSchedule {
+    pub policy: RefundPolicy,
+    pub effective: u32,
+}
+
+/// New membership terms that take over from ledger `effective`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TermSchedule {
+    pub term: MembershipTerm,
+    pub effective: u32,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Proposal {
+    pub action: CouncilAction,
+    pub proposer: Address,
+    pub approvals: Vec<Address>,
+    pub expires: u32,
+    pub executed: bool,
+}
+
+/// A membership tier. `mint_discount` is a percentage taken off the mint fee,
+/// `reward_multiplier` is a percentage of the opus reward (100 = 1x).
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Tier {
+    pub name: Symbol,
+    pub join_fee: i128,
+    pub mint_discount: u32,
+    pub reward_multiplier: u32,
+    pub actions: u32,
 }
 
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct Collective {
+   pub symbol: Symbol,
+   pub join_fee: i128,
+   pub mint_fee: i128,
+   pub pay_token: Address,
+   pub opus_reward: i128,
+}
+
+/// Layout of `Collective` before amounts moved to `i128`. Still read so
+/// existing deployments keep working until their fees are next written.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CollectiveV1 {
    pub symbol: Symbol,
    pub join_fee: u32,
    pub mint_fee: u32,
@@ -70,7 +498,10 @@ pub struct CollectiveContract;
 #[contractimpl]
 impl CollectiveContract{
     
-    pub fn __constructor(e: Env, admin: Address, join_fee: u32, mint_fee: u32, token: Address, reward: u32) {
+    pub fn __constructor(e: Env, admin: Address, join_fee: i128, mint_fee: i128, token: Address, reward: i128) {
+        require_valid_amount(join_fee);
+        require_valid_amount(mint_fee);
+        require_valid_amount(reward);
         e.storage().instance().set(&ADMIN, &admin);
 
         let collective = Collective {
@@ -85,16 +516,15 @@ impl CollectiveContract{
         e.storage().persistent().set(&Datakey::Collective, &collective);
     }
 
-    pub fn fund_contract(e: Env, caller: Address, fund_amount: u32) -> i128 {
+    pub fn fund_contract(e: Env, caller: Address, amount: i128) -> i128 {
 
         caller.require_auth();
 
-        let collective: Collective = storage_g(e.clone(), Kind::Permanent, Datakey::Collective).expect("cound not find collective");
+        let collective = read_collective(&e);
         let client = token::Client::new(&e, &collective.pay_token);
         let balance = client.balance(&caller);
-        let amount = fund_amount as i128;
 
-        if !validate_negative_amount(amount as i128) {
+        if !validate_negative_amount(amount) {
             panic!("invalid amount, must be non-negative");
         }
 
@@ -107,310 +537,2486 @@ impl CollectiveContract{
         }
 
         client.transfer(&caller, &e.current_contract_address(), &amount);
+        record_income(&e, &INCOME_DONATION, &caller, amount);
 
         amount
     }
 
     pub fn join(e: Env, caller: Address) {
+        Self::join_tier(e, caller, DEFAULT_TIER, None);
+    }
 
-        if e.storage().persistent().has(&Datakey::Member(caller.clone())) {
-            panic!("already part of collective");
-        }
+    pub fn join_tier(e: Env, caller: Address, tier: Symbol, referrer: Option<Address>) {
+
+        require_joinable(&e, &caller);
+        require_open(&e);
 
         caller.require_auth();
-        let collective: Collective = e.storage().persistent().get(&Datakey::Collective).unwrap();
+        let collective = read_collective(&e);
+        let tier = read_tier(&e, &collective, &tier).expect("unknown tier");
         let client = token::Client::new(&e, &collective.pay_token);
         let balance = client.balance(&caller);
-        let join_fee = collective.join_fee as i128;
+        let join_fee = tier.join_fee;
 
         if balance < join_fee {
             panic!("not enough to cover fee");
         }
 
         client.transfer(&caller, &e.current_contract_address(), &join_fee);
-        e.storage().persistent().set(&Datakey::Member(caller.clone()), &collective.join_fee);
-
-        e.events().publish((JOIN, symbol_short!("member")), (caller, collective.join_fee));
+        record_income(&e, &INCOME_JOIN, &caller, join_fee);
+        enroll_member(&e, &caller, &tier, tier.join_fee, referrer);
     }
 
-    pub fn withdraw(e:Env, some:Address)-> Result<bool, Error> {
-        let admin: Address = e.storage().instance().get(&ADMIN).unwrap();
-        admin.require_auth();
-        let collective: Collective = storage_g(e.clone(), Kind::Permanent, Datakey::Collective).expect("cound not find collective");
-        let client = token::Client::new(&e, &collective.pay_token);
-        let join_fee = collective.join_fee as i128;
-        let totalfees = client.balance(&e.current_contract_address());
+    pub fn join_with_voucher(e: Env, caller: Address, tier: Symbol, voucher: Voucher, signer: BytesN<32>, signature: BytesN<64>) -> i128 {
 
-        if totalfees < join_fee {
-            panic!("not enough collected to withdraw");
-        }
+        require_joinable(&e, &caller);
+        require_open(&e);
 
-        client.transfer(&e.current_contract_address(), &some, &totalfees);
+        caller.require_auth();
+        verify_voucher(&e, &caller, &voucher, &signer, &signature);
+
+        let collective = read_collective(&e);
+        let tier = read_tier(&e, &collective, &tier).expect("unknown tier");
+        let join_fee = if voucher.fee == JOIN_FEE {
+            discounted_fee(tier.join_fee, voucher.discount)
+        } else {
+            e.storage().persistent().set(&Datakey::MintCredit(caller.clone()), &voucher.discount);
+            tier.join_fee
+        };
 
-        Ok(true)
+        if join_fee > 0 {
+            deposit(&e, &caller, join_fee);
+            record_income(&e, &INCOME_JOIN, &caller, join_fee);
+        }
 
+        enroll_member(&e, &caller, &tier, join_fee, None);
+        e.events().publish((VOUCHER, symbol_short!("redeem")), (caller, voucher.fee, voucher.discount, voucher.nonce));
 
+        join_fee
     }
 
-    pub fn symbol(e: Env) -> Symbol {
-        let collective: Collective = storage_g(e.clone(), Kind::Permanent, Datakey::Collective).expect("cound not find collective");
-        collective.symbol
+    pub fn add_voucher_key(e: Env, caller: Address, key: BytesN<32>) {
+        require_role(&e, &caller, &VOUCHER_ISSUER);
+        require_no_council(&e);
+        Self::store_voucher_key(e, key);
     }
 
-    pub fn join_fee(e: Env) -> i128 {
-        let collective: Collective = storage_g(e.clone(), Kind::Permanent, Datakey::Collective).expect("cound not find collective");
-        collective.join_fee as i128
+    fn store_voucher_key(e: Env, key: BytesN<32>) {
+        e.storage().persistent().set(&Datakey::VoucherKey(key.clone()), &true);
+        e.events().publish((VOUCHER, symbol_short!("key_add")), key);
     }
 
-    pub fn mint_fee(e: Env) -> i128 {
-        let collective: Collective = storage_g(e.clone(), Kind::Permanent, Datakey::Collective).expect("cound not find collective");
-        collective.mint_fee as i128
-    }
+    pub fn remove_voucher_key(e: Env, caller: Address, key: BytesN<32>) -> bool {
+        require_role(&e, &caller, &VOUCHER_ISSUER);
 
-    pub fn opus_reward(e: Env) -> i128 {
-        let collective: Collective = storage_g(e.clone(), Kind::Permanent, Datakey::Collective).expect("cound not find collective");
-        collective.opus_reward as i128
+        if !e.storage().persistent().has(&Datakey::VoucherKey(key.clone())) {
+            return false;
+        }
+
+        e.storage().persistent().remove(&Datakey::VoucherKey(key.clone()));
+        e.events().publish((VOUCHER, symbol_short!("key_del")), key);
+        true
     }
 
-    pub fn member_paid(e: Env, caller: Address) -> u32 {
-        caller.require_auth();
-        e.storage().persistent().get(&Datakey::Member(caller)).unwrap_or(0)
+    pub fn is_voucher_used(e: Env, signer: BytesN<32>, nonce: u64) -> bool {
+        e.storage().persistent().has(&Datakey::VoucherNonce(signer, nonce))
     }
 
-    pub fn opus_address(e: Env)-> Address {
-        let address: Address = e.storage().instance().get::<Symbol, Address>(&OPUS).expect("network not initialized");
-        address
+    pub fn mint_credit(e: Env, caller: Address) -> u32 {
+        e.storage().persistent().get(&Datakey::MintCredit(caller)).unwrap_or(0)
     }
 
-    pub fn is_member(e: Env, caller: Address) -> bool {
-        let this_contract = e.current_contract_address();
-        let admin: Address = e.storage().instance().get(&ADMIN).unwrap();
+    pub fn sponsor_join(e: Env, sponsor: Address, new_member: Address, tier: Symbol) {
 
-            caller == admin || caller == this_contract || e.storage().persistent().has(&Datakey::Member(caller))
-    }
+        require_joinable(&e, &new_member);
+        require_open(&e);
 
-    pub fn remove(e:Env, caller: Address)-> bool{
-        let admin: Address = e.storage().instance().get(&ADMIN).unwrap();
-        admin.require_auth();
+        sponsor.require_auth();
+        let collective = read_collective(&e);
+        let tier = read_tier(&e, &collective, &tier).expect("unknown tier");
+        let client = token::Client::new(&e, &collective.pay_token);
+        let join_fee = tier.join_fee;
 
-        if !e.storage().persistent().has(&Datakey::Member(caller.clone())) {
-            return false;
+        if client.balance(&sponsor) < join_fee {
+            panic!("not enough to cover fee");
         }
 
-        e.storage().persistent().remove(&Datakey::Member(caller.clone()));
-        e.events().publish((REMOVE, symbol_short!("member")), caller);
-        true
+        client.transfer(&sponsor, &e.current_contract_address(), &join_fee);
+        record_income(&e, &INCOME_JOIN, &sponsor, join_fee);
+        // the newcomer paid nothing, so leaving must not refund the sponsor's fee
+        enroll_member(&e, &new_member, &tier, 0, None);
+
+        e.events().publish((SPONSOR, symbol_short!("join")), (sponsor, new_member, join_fee));
     }
 
-    pub fn deploy_node_token(e:Env, caller: Address, name: String, descriptor: String)-> Address{
+    pub fn create_pool(e: Env, funder: Address, amount: i128) -> u32 {
 
-        caller.require_auth();
+        funder.require_auth();
+        deposit(&e, &funder, amount);
 
-        if !Self::is_member(e.clone(), caller.clone()) {
-            panic!("unauthorized");
-        }
+        let id: u32 = e.storage().persistent().get(&Datakey::PoolCount).unwrap_or(0);
+        let pool = SponsorPool {
+            funder: funder.clone(),
+            balance: amount,
+        };
 
-        let ledger = e.ledger();
-        let symbol = String::from_val(&e, &"HVYMNODE");
-        let b: [u8; 32] = hash_string(&e, &name).into();
-        let node_id = String::from_bytes(&e, &b);
-        let established = ledger.timestamp();
-        let wasm_hash = e.deployer().upload_contract_wasm(pintheon_node_token::WASM);
-        let str_addr = Address::to_string(&caller);
-        let salt = hash_string(&e, &str_addr);
-        let constructor_args: Vec<Val> = (caller.clone(), 0u32, name.clone(), symbol.clone(), node_id.clone(), descriptor.clone(), established.clone()).into_val(&e);
+        reserve(&e, amount);
+        e.storage().persistent().set(&Datakey::Pool(id), &pool);
+        e.storage().persistent().set(&Datakey::PoolCount, &(id + 1));
+        e.events().publish((SPONSOR, symbol_short!("pool")), (id, funder, amount));
 
-        let contract_id = Self::deploy_contract(e.clone(), caller.clone(), wasm_hash.clone(), salt.clone(), constructor_args.clone());
-        let token = pintheon_node_token::Client::new(&e, &contract_id);
-        token.mint(&caller, &1);
+        id
+    }
 
-        contract_id
+    pub fn fund_pool(e: Env, funder: Address, pool_id: u32, amount: i128) -> i128 {
+
+        funder.require_auth();
+        let mut pool: SponsorPool = e.storage().persistent().get(&Datakey::Pool(pool_id)).expect("unknown pool");
+        deposit(&e, &funder, amount);
+
+        pool.balance += amount;
+        reserve(&e, amount);
+        e.storage().persistent().set(&Datakey::Pool(pool_id), &pool);
+        e.events().publish((SPONSOR, symbol_short!("fund")), (pool_id, funder, amount));
+
+        pool.balance
     }
 
-    pub fn deploy_ipfs_token(e:Env, caller: Address, name: String, ipfs_hash: String, file_type: String, gateways: String, _ipns_hash: Option<String>)-> Address{
+    pub fn allocate_pool(e: Env, caller: Address, pool_id: u32, new_member: Address, tier: Symbol) -> i128 {
+        require_role(&e, &caller, &MODERATOR);
+        require_joinable(&e, &new_member);
 
-        if Self::is_launched(e.clone()) == false {
-            panic!("network not up");
+        let mut pool: SponsorPool = e.storage().persistent().get(&Datakey::Pool(pool_id)).expect("unknown pool");
+        let collective = read_collective(&e);
+        let tier = read_tier(&e, &collective, &tier).expect("unknown tier");
+        let join_fee = tier.join_fee;
+
+        if pool.balance < join_fee {
+            panic!("not enough in pool");
         }
 
-        caller.require_auth();
+        pool.balance -= join_fee;
+        release(&e, join_fee);
+        record_income(&e, &INCOME_JOIN, &pool.funder, join_fee);
+        e.storage().persistent().set(&Datakey::Pool(pool_id), &pool);
+        enroll_member(&e, &new_member, &tier, 0, None);
 
-        if !Self::is_member(e.clone(), caller.clone()) {
+        e.events().publish((SPONSOR, symbol_short!("allocate")), (pool_id, new_member, join_fee));
+
+        pool.balance
+    }
+
+    pub fn close_pool(e: Env, funder: Address, pool_id: u32) -> i128 {
+
+        funder.require_auth();
+        let pool: SponsorPool = e.storage().persistent().get(&Datakey::Pool(pool_id)).expect("unknown pool");
+
+        if pool.funder != funder {
             panic!("unauthorized");
         }
-        
-        let collective: Collective = storage_g(e.clone(), Kind::Permanent, Datakey::Collective).expect("cound not find collective");
-        let client = token::Client::new(&e, &collective.pay_token);
-        let balance = client.balance(&caller);
-        let mint_fee = collective.mint_fee as i128;
 
-        if balance < mint_fee {
-            panic!("not enough to cover fee");
+        if pool.balance > 0 {
+            let collective = read_collective(&e);
+            let client = token::Client::new(&e, &collective.pay_token);
+            release(&e, pool.balance);
+            client.transfer(&e.current_contract_address(), &funder, &pool.balance);
         }
 
-        client.transfer(&caller, &e.current_contract_address(), &mint_fee);
+        e.storage().persistent().remove(&Datakey::Pool(pool_id));
+        e.events().publish((SPONSOR, symbol_short!("close")), (pool_id, funder, pool.balance));
 
-        let ledger = e.ledger();
-        let symbol = String::from_val(&e, &"HVYMFILE");
-        let published = ledger.timestamp();
-        let wasm_hash = e.deployer().upload_contract_wasm(pintheon_ipfs_token::WASM);
-        let salt = hash_string(&e, &ipfs_hash);
-        let constructor_args: Vec<Val> = (caller.clone(), 0u32, name.clone(), symbol.clone(), ipfs_hash.clone(), file_type.clone(), published.clone(), gateways.clone(), _ipns_hash.clone()).into_val(&e);
+        pool.balance
+    }
 
-        let contract_id = Self::deploy_contract(e.clone(), caller.clone(), wasm_hash.clone(), salt.clone(), constructor_args.clone());
+    pub fn pool(e: Env, pool_id: u32) -> SponsorPool {
+        e.storage().persistent().get(&Datakey::Pool(pool_id)).expect("unknown pool")
+    }
 
-        //mint opus reward to caller
-        let opus_address: Address = e.storage().instance().get(&OPUS).unwrap();
-        let opus_client = token::StellarAssetClient::new(&e, &opus_address);
-        let reward = collective.opus_reward as i128;
+    pub fn set_join_mode(e: Env, mode: JoinMode) -> JoinMode {
+        let admin: Address = e.storage().instance().get(&ADMIN).unwrap();
+        admin.require_auth();
+        require_no_council(&e);
+        Self::store_join_mode(e, mode)
+    }
 
-        opus_client.mint(&caller, &reward);
+    fn store_join_mode(e: Env, mode: JoinMode) -> JoinMode {
+        e.storage().persistent().set(&Datakey::JoinMode, &mode);
+        mode
+    }
 
-        contract_id
+    pub fn join_mode(e: Env) -> JoinMode {
+        read_join_mode(&e)
     }
 
-    pub fn publish_file(e: Env, caller: Address, ipfs_hash: String) {
+    pub fn apply(e: Env, caller: Address, tier: Symbol, portfolio: String) -> Application {
 
-        caller.require_auth();
-        let collective: Collective = e.storage().persistent().get(&Datakey::Collective).unwrap();
-        let client = token::Client::new(&e, &collective.pay_token);
-        let balance = client.balance(&caller);
-        let mint_fee = collective.mint_fee as i128;
+        require_joinable(&e, &caller);
 
-        if balance < mint_fee {
-            panic!("not enough to cover fee");
+        if read_join_mode(&e) != JoinMode::Gated {
+            panic!("collective is open");
         }
 
-        client.transfer(&caller, &e.current_contract_address(), &mint_fee);
-
-        e.events().publish((PUBLISH, symbol_short!("file")), (caller, ipfs_hash));
-    }
-
-    pub fn publish_encrypted_share(e: Env, caller: Address, recipient: Address, ipfs_hash: String) {
+        if e.storage().persistent().has(&Datakey::Application(caller.clone())) {
+            panic!("application pending");
+        }
 
         caller.require_auth();
-        let collective: Collective = e.storage().persistent().get(&Datakey::Collective).unwrap();
-        let client = token::Client::new(&e, &collective.pay_token);
-        let balance = client.balance(&caller);
-        let mint_fee = collective.mint_fee as i128;
+        let collective = read_collective(&e);
+        let tier = read_tier(&e, &collective, &tier).expect("unknown tier");
+        let escrow = tier.join_fee;
 
-        if balance < mint_fee {
-            panic!("not enough to cover fee");
+        if escrow > 0 {
+            deposit(&e, &caller, escrow);
+            reserve(&e, escrow);
         }
 
-        client.transfer(&caller, &e.current_contract_address(), &mint_fee);
+        let application = Application {
+            tier: tier.name,
+            portfolio: portfolio.clone(),
+            escrow,
+            submitted: e.ledger().timestamp(),
+        };
 
-        e.events().publish((PUBLISH, symbol_short!("encrypted")), (caller, recipient, ipfs_hash));
+        e.storage().persistent().set(&Datakey::Application(caller.clone()), &application);
+        e.events().publish((APPLY, symbol_short!("submit")), (caller, portfolio, escrow));
+
+        application
     }
 
-    pub fn launch_opus(e:Env, initial_alloc: u32)-> Address{
+    pub fn approve_application(e: Env, caller: Address, applicant: Address) {
+        require_role(&e, &caller, &MODERATOR);
+        require_joinable(&e, &applicant);
 
-        if Self::is_launched(e.clone()) {
-            panic!("opus already up");
-        }
+        let application: Application = e.storage().persistent().get(&Datakey::Application(applicant.clone())).expect("no application");
+        let collective = read_collective(&e);
+        let tier = read_tier(&e, &collective, &application.tier).expect("unknown tier");
 
-        let admin: Address = e.storage().instance().get(&ADMIN).unwrap();
-        admin.require_auth();
-        let wasm_hash = e.deployer().upload_contract_wasm(opus_token::WASM);
-        let str_addr = Address::to_string(&admin);
-        let salt = hash_string(&e, &str_addr);
-        let this_contract = &e.current_contract_address();
-        let constructor_args: Vec<Val> = (this_contract.clone(),).into_val(&e);
+        // the escrowed fee becomes part of the treasury
+        release(&e, application.escrow);
+        record_income(&e, &INCOME_JOIN, &applicant, application.escrow);
+        e.storage().persistent().remove(&Datakey::Application(applicant.clone()));
+        enroll_member(&e, &applicant, &tier, application.escrow, None);
 
-        let contract_id = Self::deploy_contract(e.clone(), this_contract.clone(), wasm_hash.clone(), salt.clone(), constructor_args.clone());
-        let allocation = initial_alloc as i128;
-        e.storage().instance().set(&OPUS, &contract_id);
-        let token = opus_token::Client::new(&e, &contract_id);
+        e.events().publish((APPLY, symbol_short!("approve")), applicant);
+    }
 
-        token.mint(&admin, &allocation);
+    pub fn reject_application(e: Env, caller: Address, applicant: Address) -> i128 {
+        require_role(&e, &caller, &MODERATOR);
 
-        contract_id
+        let refund = refund_application(&e, &applicant);
+        e.events().publish((APPLY, symbol_short!("reject")), (applicant, refund));
 
+        refund
     }
 
-    pub fn is_launched(e: Env) -> bool {
-        let launched = if e.storage().instance().get::<_, Address>(&OPUS).is_some() { true } else { false }; 
-
-        launched
-    }
+    pub fn withdraw_application(e: Env, caller: Address) -> i128 {
 
-    pub fn update_join_fee(e: Env, new_fee: u32) -> i128 {
-        let admin: Address = e.storage().instance().get(&ADMIN).unwrap();
-        admin.require_auth();
-        let  mut collective: Collective = storage_g(e.clone(), Kind::Permanent, Datakey::Collective).expect("cound find collective");
-        collective.join_fee = new_fee;
+        caller.require_auth();
 
-        if !validate_negative_amount(new_fee as i128) {
-            panic!("invalid amount, must be non-negative");
-        }
+        let refund = refund_application(&e, &caller);
+        e.events().publish((APPLY, symbol_short!("withdraw")), (caller, refund));
 
-        storage_p(e, collective, Kind::Permanent, Datakey::Collective);
-        new_fee as i128
+        refund
     }
 
-    pub fn update_mint_fee(e: Env, new_fee: u32) -> i128 {
-        let admin: Address = e.storage().instance().get(&ADMIN).unwrap();
-        admin.require_auth();
-        let  mut collective: Collective = storage_g(e.clone(), Kind::Permanent, Datakey::Collective).expect("cound find collective");
-        collective.join_fee = new_fee;
+    pub fn application(e: Env, caller: Address) -> Option<Application> {
+        e.storage().persistent().get(&Datakey::Application(caller))
+    }
 
-        if !validate_negative_amount(new_fee as i128) {
-            panic!("invalid amount, must be non-negative");
+    pub fn treasury_report(e: Env) -> TreasuryReport {
+        let collective = read_collective(&e);
+        let client = token::Client::new(&e, &collective.pay_token);
+        let reserved: i128 = e.storage().persistent().get(&Datakey::Reserved).unwrap_or(0);
+
+        TreasuryReport {
+            joins: ledger_total(&e, Datakey::Income(INCOME_JOIN)),
+            renewals: ledger_total(&e, Datakey::Income(INCOME_RENEW)),
+            mints: ledger_total(&e, Datakey::Income(INCOME_MINT)),
+            publishes: ledger_total(&e, Datakey::Income(INCOME_PUBLISH)),
+            shares: ledger_total(&e, Datakey::Income(INCOME_SHARE)),
+            donations: ledger_total(&e, Datakey::Income(INCOME_DONATION)),
+            withdrawals: ledger_total(&e, Datakey::Outflow(OUTFLOW_WITHDRAW)),
+            refunds: ledger_total(&e, Datakey::Outflow(OUTFLOW_REFUND)),
+            dividends: ledger_total(&e, Datakey::Outflow(OUTFLOW_DIVIDEND)),
+            reserved,
+            balance: client.balance(&e.current_contract_address()),
         }
+    }
 
-        storage_p(e, collective, Kind::Permanent, Datakey::Collective);
-        new_fee as i128
+    pub fn income(e: Env, category: Symbol) -> i128 {
+        ledger_total(&e, Datakey::Income(category))
     }
 
-    pub fn update_opus_reward(e: Env, new_reward: u32) -> i128 {
-        let admin: Address = e.storage().instance().get(&ADMIN).unwrap();
+    pub fn income_from(e: Env, caller: Address) -> i128 {
+        ledger_total(&e, Datakey::IncomeFrom(caller))
+    }
+
+    pub fn withdrawn_by(e: Env, recipient: Address) -> i128 {
+        ledger_total(&e, Datakey::Withdrawn(recipient))
+    }
+
+    pub fn reserved(e: Env) -> i128 {
+        e.storage().persistent().get(&Datakey::Reserved).unwrap_or(0)
+    }
+
+    pub fn upgrade_tier(e: Env, caller: Address, tier: Symbol) {
+
+        caller.require_auth();
+        let mut member = read_member(&e, &caller).expect("not a member");
+
+        if member_status(&e, &member) == MemberStatus::Expired {
+            panic!("membership expired");
+        }
+
+        if member.tier == tier {
+            panic!("already in tier");
+        }
+
+        let collective = read_collective(&e);
+        let tier = read_tier(&e, &collective, &tier).expect("unknown tier");
+
+        // members pay the difference between what they have paid and the new tier fee
+        let owed = (tier.join_fee - member.paid).max(0);
+
+        if owed > 0 {
+            let client = token::Client::new(&e, &collective.pay_token);
+
+            if client.balance(&caller) < owed {
+                panic!("not enough to cover fee");
+            }
+
+            client.transfer(&caller, &e.current_contract_address(), &owed);
+            record_income(&e, &INCOME_JOIN, &caller, owed);
+        }
+
+        member.paid += owed;
+        member.tier = tier.name.clone();
+        e.storage().persistent().set(&Datakey::Member(caller.clone()), &member);
+
+        e.events().publish((TIER, symbol_short!("upgrade")), (caller, tier.name, owed));
+    }
+
+    pub fn renew(e: Env, caller: Address) -> u64 {
+
+        caller.require_auth();
+        let mut member = read_member(&e, &caller).expect("not a member");
+        let term = read_term(&e);
+
+        if term.duration == 0 || member.expires == 0 {
+            panic!("membership does not expire");
+        }
+
+        let collective = read_collective(&e);
+        let client = token::Client::new(&e, &collective.pay_token);
+        let renew_fee = term.renew_fee;
+
+        if client.balance(&caller) < renew_fee {
+            panic!("not enough to cover fee");
+        }
+
+        client.transfer(&caller, &e.current_contract_address(), &renew_fee);
+        record_income(&e, &INCOME_RENEW, &caller, renew_fee);
+
+        // renewing within the grace period continues the current term, a lapsed
+        // membership starts a fresh one
+        let now = e.ledger().timestamp();
+        member.expires = if now <= member.expires + term.grace {
+            member.expires + term.duration
+        } else {
+            now + term.duration
+        };
+
+        e.storage().persistent().set(&Datakey::Member(caller.clone()), &member);
+        e.events().publish((RENEW, symbol_short!("member")), (caller, member.expires));
+
+        member.expires
+    }
+
+    pub fn set_membership_term(e: Env, caller: Address, duration: u64, grace: u64, renew_fee: i128) -> MembershipTerm {
+        require_role(&e, &caller, &FEE_MANAGER);
+        require_no_council(&e);
+        Self::store_term(e, MembershipTerm { duration, grace, renew_fee })
+    }
+
+    // The first term applies straight away since nobody renews under it yet;
+    // later changes give members FEE_NOTICE ledgers of warning.
+    fn store_term(e: Env, term: MembershipTerm) -> MembershipTerm {
+        require_valid_amount(term.renew_fee);
+
+        if !e.storage().persistent().has(&Datakey::Term) {
+            e.storage().persistent().set(&Datakey::Term, &term);
+            return term;
+        }
+
+        let current = settle_term(&e);
+        let schedule = TermSchedule { term: term.clone(), effective: e.ledger().sequence() + FEE_NOTICE };
+
+        e.storage().persistent().set(&Datakey::TermSchedule, &schedule);
+        e.events().publish((FEES, symbol_short!("term")), (current, schedule));
+        term
+    }
+
+    /// Membership terms that have been scheduled but are not yet in force.
+    pub fn pending_term(e: Env) -> Option<TermSchedule> {
+        e.storage().persistent().get::<_, TermSchedule>(&Datakey::TermSchedule)
+            .filter(|schedule| schedule.effective > e.ledger().sequence())
+    }
+
+    pub fn membership_term(e: Env) -> MembershipTerm {
+        read_term(&e)
+    }
+
+    pub fn member_status(e: Env, caller: Address) -> MemberStatus {
+        match read_member(&e, &caller) {
+            Some(member) => member_status(&e, &member),
+            None => MemberStatus::NotMember,
+        }
+    }
+
+    pub fn member_count(e: Env) -> u32 {
+        e.storage().persistent().get(&Datakey::MemberCount).unwrap_or(0)
+    }
+
+    /// Adds members stored before the registry existed to the index. Anyone
+    /// not a member or already indexed is skipped. Returns how many were
+    /// added.
+    pub fn index_members(e: Env, members: Vec<Address>) -> u32 {
+        let admin: Address = e.storage().instance().get(&ADMIN).unwrap();
+        admin.require_auth();
+
+        if members.len() > MAX_PAGE {
+            panic!("too many members");
+        }
+
+        let mut indexed = 0;
+
+        for address in members.iter() {
+            if e.storage().persistent().has(&Datakey::MemberIndex(address.clone())) {
+                continue;
+            }
+
+            if let Some(member) = read_member(&e, &address) {
+                add_member(&e, &member);
+                indexed += 1;
+            }
+        }
+
+        indexed
+    }
+
+    pub fn members(e: Env, start: u32, limit: u32) -> Vec<Member> {
+        let count: u32 = e.storage().persistent().get(&Datakey::MemberCount).unwrap_or(0);
+        let end = count.min(start.saturating_add(limit.min(MAX_PAGE)));
+        let mut members = Vec::new(&e);
+
+        for index in start..end {
+            let address: Address = e.storage().persistent().get(&Datakey::MemberAt(index)).unwrap();
+            members.push_back(read_member(&e, &address).unwrap());
+        }
+
+        members
+    }
+
+    pub fn member(e: Env, caller: Address) -> Member {
+        read_member(&e, &caller).expect("not a member")
+    }
+
+    pub fn update_profile(e: Env, caller: Address, handle: String, avatar: String, bio: String) -> Profile {
+
+        caller.require_auth();
+
+        if read_member(&e, &caller).is_none() {
+            panic!("not a member");
+        }
+
+        let mut profile = read_profile(&e, &caller);
+        profile.handle = handle;
+        profile.avatar = avatar;
+        profile.bio = bio;
+
+        e.storage().persistent().set(&Datakey::Profile(caller.clone()), &profile);
+        e.events().publish((PROFILE, symbol_short!("update")), caller);
+
+        profile
+    }
+
+    pub fn profile(e: Env, caller: Address) -> Profile {
+        read_profile(&e, &caller)
+    }
+
+    pub fn node_token_count(e: Env, caller: Address) -> u32 {
+        profile_token_count(&e, &caller, &NODE_TOKEN)
+    }
+
+    pub fn node_tokens(e: Env, caller: Address, start: u32, limit: u32) -> Vec<Address> {
+        read_profile_tokens(&e, &caller, &NODE_TOKEN, start, limit)
+    }
+
+    pub fn file_token_count(e: Env, caller: Address) -> u32 {
+        profile_token_count(&e, &caller, &FILE_TOKEN)
+    }
+
+    pub fn file_tokens(e: Env, caller: Address, start: u32, limit: u32) -> Vec<Address> {
+        read_profile_tokens(&e, &caller, &FILE_TOKEN, start, limit)
+    }
+
+    pub fn member_expires(e: Env, caller: Address) -> u64 {
+        read_member(&e, &caller).expect("not a member").expires
+    }
+
+    pub fn set_referral_policy(e: Env, caller: Address, bonus: i128, cap: u32, period: u64) -> ReferralPolicy {
+        require_role(&e, &caller, &FEE_MANAGER);
+        require_no_council(&e);
+        Self::store_referral_policy(e, ReferralPolicy { bonus, cap, period })
+    }
+
+    fn store_referral_policy(e: Env, policy: ReferralPolicy) -> ReferralPolicy {
+        require_valid_amount(policy.bonus);
+        e.storage().persistent().set(&Datakey::ReferralPolicy, &policy);
+        policy
+    }
+
+    pub fn referral_policy(e: Env) -> ReferralPolicy {
+        read_referral_policy(&e)
+    }
+
+    pub fn referral_count(e: Env, caller: Address) -> u32 {
+        e.storage().persistent().get::<_, ReferralStats>(&Datakey::Referrals(caller)).map(|stats| stats.count).unwrap_or(0)
+    }
+
+    pub fn referrer_of(e: Env, caller: Address) -> Option<Address> {
+        e.storage().persistent().get(&Datakey::Referrer(caller))
+    }
+
+    pub fn set_tier(e: Env, caller: Address, name: Symbol, join_fee: i128, mint_discount: u32, reward_multiplier: u32, actions: u32) -> Tier {
+        require_role(&e, &caller, &FEE_MANAGER);
+        require_no_council(&e);
+        Self::store_tier(e, Tier { name, join_fee, mint_discount, reward_multiplier, actions })
+    }
+
+    fn store_tier(e: Env, tier: Tier) -> Tier {
+        require_valid_amount(tier.join_fee);
+
+        if tier.name == DEFAULT_TIER {
+            panic!("default tier follows collective fees");
+        }
+
+        if tier.mint_discount > 100 {
+            panic!("discount must not exceed 100");
+        }
+
+        if tier.actions & !ALL_ACTIONS != 0 {
+            panic!("unknown action");
+        }
+
+        let name = tier.name.clone();
+
+        // members of an existing tier get notice before its terms change
+        if let Some(current) = settle_tier(&e, &name) {
+            let schedule = TierSchedule { tier: tier.clone(), removed: false, effective: e.ledger().sequence() + FEE_NOTICE };
+            e.storage().persistent().set(&Datakey::TierSchedule(name.clone()), &schedule);
+            e.events().publish((TIER, symbol_short!("schedule")), (current, schedule));
+            return tier;
+        }
+
+        let mut tiers: Vec<Symbol> = e.storage().persistent().get(&Datakey::Tiers).unwrap_or(Vec::new(&e));
+        if !tiers.contains(&name) {
+            tiers.push_back(name.clone());
+            e.storage().persistent().set(&Datakey::Tiers, &tiers);
+        }
+
+        e.storage().persistent().set(&Datakey::Tier(name.clone()), &tier);
+        e.events().publish((TIER, symbol_short!("set")), (name, tier.join_fee));
+
+        tier
+    }
+
+    /// Change to tier `name` that has been scheduled but is not yet in force.
+    pub fn pending_tier(e: Env, name: Symbol) -> Option<TierSchedule> {
+        e.storage().persistent().get::<_, TierSchedule>(&Datakey::TierSchedule(name))
+            .filter(|schedule| schedule.effective > e.ledger().sequence())
+    }
+
+    /// Schedules tier `name` for removal once FEE_NOTICE ledgers have passed.
+    pub fn remove_tier(e: Env, caller: Address, name: Symbol) -> bool {
+        require_role(&e, &caller, &FEE_MANAGER);
+        require_no_council(&e);
+        Self::drop_tier(e, name)
+    }
+
+    // Members of the tier keep it for FEE_NOTICE ledgers before they drop
+    // to the default tier.
+    fn drop_tier(e: Env, name: Symbol) -> bool {
+        let tier = match settle_tier(&e, &name) {
+            Some(tier) => tier,
+            None => return false,
+        };
+
+        let schedule = TierSchedule { tier, removed: true, effective: e.ledger().sequence() + FEE_NOTICE };
+        e.storage().persistent().set(&Datakey::TierSchedule(name.clone()), &schedule);
+        e.events().publish((TIER, symbol_short!("remove")), (name, schedule.effective));
+        true
+    }
+
+    pub fn tier(e: Env, name: Symbol) -> Tier {
+        let collective = read_collective(&e);
+        read_tier(&e, &collective, &name).expect("unknown tier")
+    }
+
+    pub fn tiers(e: Env) -> Vec<Symbol> {
+        let collective = read_collective(&e);
+        let mut tiers = Vec::new(&e);
+        tiers.push_back(DEFAULT_TIER);
+
+        let stored: Vec<Symbol> = e.storage().persistent().get(&Datakey::Tiers).unwrap_or(Vec::new(&e));
+        for name in stored.iter() {
+            if read_tier(&e, &collective, &name).is_some() {
+                tiers.push_back(name);
+            }
+        }
+
+        tiers
+    }
+
+    pub fn member_tier(e: Env, caller: Address) -> Symbol {
+        read_member(&e, &caller).expect("not a member").tier
+    }
+
+    pub fn withdraw(e:Env, caller: Address, some:Address)-> Result<bool, Error> {
+        require_role(&e, &caller, &TREASURER);
+        require_no_council(&e);
+        Self::withdraw_all(e, some);
+
+        Ok(true)
+    }
+
+    fn withdraw_all(e: Env, some: Address) -> i128 {
+        let collective = read_collective(&e);
+        let client = token::Client::new(&e, &collective.pay_token);
+        let join_fee = collective.join_fee;
+        let totalfees = available_balance(&e, &client);
+
+        if totalfees < join_fee {
+            panic!("not enough collected to withdraw");
+        }
+
+        client.transfer(&e.current_contract_address(), &some, &totalfees);
+        record_withdrawal(&e, &some, totalfees);
+
+        totalfees
+    }
+
+    pub fn grant_role(e: Env, role: Symbol, account: Address) {
+        let admin: Address = e.storage().instance().get(&ADMIN).unwrap();
+        admin.require_auth();
+        require_no_council(&e);
+        Self::assign_role(e, role, account);
+    }
+
+    fn assign_role(e: Env, role: Symbol, account: Address) {
+        if role != TREASURER && role != MODERATOR && role != FEE_MANAGER && role != LAUNCHER && role != VOUCHER_ISSUER {
+            panic!("unknown role");
+        }
+
+        e.storage().persistent().set(&Datakey::Role(role.clone(), account.clone()), &true);
+        e.events().publish((ROLE, symbol_short!("grant")), (role, account));
+    }
+
+    pub fn revoke_role(e: Env, role: Symbol, account: Address) -> bool {
+        let admin: Address = e.storage().instance().get(&ADMIN).unwrap();
+        admin.require_auth();
+        require_no_council(&e);
+        Self::unassign_role(e, role, account)
+    }
+
+    fn unassign_role(e: Env, role: Symbol, account: Address) -> bool {
+        if !has_role(&e, &role, &account) {
+            return false;
+        }
+
+        e.storage().persistent().remove(&Datakey::Role(role.clone(), account.clone()));
+        e.events().publish((ROLE, symbol_short!("revoke")), (role, account));
+        true
+    }
+
+    pub fn has_role(e: Env, role: Symbol, account: Address) -> bool {
+        has_role(&e, &role, &account)
+    }
+
+    /// Starts an admin handover. Nothing changes until the proposed admin
+    /// accepts, so a mistyped address can simply be cancelled.
+    pub fn propose_admin(e: Env, new_admin: Address) {
+        let admin: Address = e.storage().instance().get(&ADMIN).unwrap();
+        admin.require_auth();
+        require_no_council(&e);
+        Self::nominate_admin(e, new_admin);
+    }
+
+    fn nominate_admin(e: Env, new_admin: Address) {
+        let admin: Address = e.storage().instance().get(&ADMIN).unwrap();
+        e.storage().persistent().set(&Datakey::PendingAdmin, &new_admin);
+        e.events().publish((ADMIN_EVENT, symbol_short!("propose")), (admin, new_admin));
+    }
+
+    pub fn accept_admin(e: Env) {
+        let new_admin: Address = e.storage().persistent().get(&Datakey::PendingAdmin).expect("no pending admin");
+        new_admin.require_auth();
+
+        let admin: Address = e.storage().instance().get(&ADMIN).unwrap();
+        e.storage().instance().set(&ADMIN, &new_admin);
+        e.storage().persistent().set(&Datakey::Admin, &new_admin);
+        e.storage().persistent().remove(&Datakey::PendingAdmin);
+        e.events().publish((ADMIN_EVENT, symbol_short!("accept")), (admin, new_admin));
+    }
+
+    pub fn cancel_admin_proposal(e: Env) {
+        let admin: Address = e.storage().instance().get(&ADMIN).unwrap();
+        admin.require_auth();
+        require_no_council(&e);
+        Self::withdraw_admin_proposal(e);
+    }
+
+    fn withdraw_admin_proposal(e: Env) {
+        let admin: Address = e.storage().instance().get(&ADMIN).unwrap();
+        let pending: Address = e.storage().persistent().get(&Datakey::PendingAdmin).expect("no pending admin");
+        e.storage().persistent().remove(&Datakey::PendingAdmin);
+        e.events().publish((ADMIN_EVENT, symbol_short!("cancel")), (admin, pending));
+    }
+
+    pub fn admin(e: Env) -> Address {
+        e.storage().instance().get(&ADMIN).unwrap()
+    }
+
+    pub fn pending_admin(e: Env) -> Option<Address> {
+        e.storage().persistent().get(&Datakey::PendingAdmin)
+    }
+
+    /// Switches the collective to council mode. From then on withdrawals, fee,
+    /// tier, term and policy updates, removals, bans, roles, voucher keys,
+    /// admin transfers, the opus launch, vesting grants and the staking
+    /// contract only change through approved proposals, and the council can
+    /// only be changed by its own proposal. Revoking a voucher key stays open
+    /// to its issuers so a leaked key can be shut off at once.
+    pub fn set_council(e: Env, signers: Vec<Address>, threshold: u32, window: u32) {
+        let admin: Address = e.storage().instance().get(&ADMIN).unwrap();
+        admin.require_auth();
+
+        if e.storage().persistent().has(&Datakey::Council) {
+            panic!("council already set");
+        }
+
+        store_council(&e, signers, threshold, window);
+    }
+
+    pub fn council(e: Env) -> Option<Council> {
+        e.storage().persistent().get(&Datakey::Council)
+    }
+
+    pub fn propose(e: Env, signer: Address, action: CouncilAction) -> u32 {
+        signer.require_auth();
+        let council = read_council(&e);
+
+        if !council.signers.contains(&signer) {
+            panic!("not a council signer");
+        }
+
+        let id: u32 = e.storage().persistent().get(&Datakey::ProposalCount).unwrap_or(0);
+        let proposal = Proposal {
+            action,
+            proposer: signer.clone(),
+            approvals: Vec::from_array(&e, [signer.clone()]),
+            expires: e.ledger().sequence() + council.window,
+            executed: false,
+        };
+
+        e.storage().persistent().set(&Datakey::Proposal(id), &proposal);
+        e.storage().persistent().set(&Datakey::ProposalCount, &(id + 1));
+        e.events().publish((COUNCIL, symbol_short!("propose")), (id, signer));
+
+        id
+    }
+
+    pub fn approve(e: Env, signer: Address, id: u32) -> u32 {
+        signer.require_auth();
+        let council = read_council(&e);
+
+        if !council.signers.contains(&signer) {
+            panic!("not a council signer");
+        }
+
+        let mut proposal = read_open_proposal(&e, id);
+
+        if proposal.approvals.contains(&signer) {
+            panic!("already approved");
+        }
+
+        proposal.approvals.push_back(signer.clone());
+        e.storage().persistent().set(&Datakey::Proposal(id), &proposal);
+        e.events().publish((COUNCIL, symbol_short!("approve")), (id, signer));
+
+        approval_count(&council, &proposal)
+    }
+
+    /// Runs a proposal once enough current signers have approved it. Anyone
+    /// may trigger execution.
+    pub fn execute(e: Env, id: u32) {
+        let council = read_council(&e);
+        let mut proposal = read_open_proposal(&e, id);
+
+        if approval_count(&council, &proposal) < council.threshold {
+            panic!("not enough approvals");
+        }
+
+        proposal.executed = true;
+        e.storage().persistent().set(&Datakey::Proposal(id), &proposal);
+
+        match proposal.action {
+            CouncilAction::Withdraw(to) => {
+                Self::withdraw_all(e.clone(), to);
+            }
+            CouncilAction::WithdrawAmount(to, amount) => {
+                Self::pay_out(e.clone(), to, amount);
+            }
+            CouncilAction::SetSplits(splits) => Self::store_splits(e.clone(), splits),
+            CouncilAction::Distribute => {
+                Self::distribute_splits(e.clone());
+            }
+            CouncilAction::UpdateJoinFee(fee) => {
+                Self::set_join_fee(e.clone(), fee);
+            }
+            CouncilAction::UpdateMintFee(fee) => {
+                Self::set_mint_fee(e.clone(), fee);
+            }
+            CouncilAction::UpdateOpusReward(reward) => {
+                Self::set_opus_reward(e.clone(), reward);
+            }
+            CouncilAction::Remove(member) => {
+                Self::remove_member(e.clone(), member);
+            }
+            CouncilAction::LaunchOpus(initial_alloc, cap, epoch_length, epoch_emission) => {
+                Self::deploy_opus(e.clone(), initial_alloc, cap, epoch_length, epoch_emission);
+            }
+            CouncilAction::SetCouncil(signers, threshold, window) => store_council(&e, signers, threshold, window),
+            CouncilAction::ScheduleFees(schedule) => Self::queue_fees(e.clone(), schedule),
+            CouncilAction::DeclareDividend(amount) => {
+                Self::earmark_dividend(e.clone(), amount);
+            }
+            CouncilAction::Ban(member, reason, expires) => {
+                Self::ban_member(e.clone(), member, reason, expires);
+            }
+            CouncilAction::SetTier(tier) => {
+                Self::store_tier(e.clone(), tier);
+            }
+            CouncilAction::SetTerm(term) => {
+                Self::store_term(e.clone(), term);
+            }
+            CouncilAction::ProposeAdmin(new_admin) => Self::nominate_admin(e.clone(), new_admin),
+            CouncilAction::GrantVesting(beneficiary, amount, cliff, duration, revocable) => {
+                Self::allocate_grant(e.clone(), beneficiary, amount, cliff, duration, revocable);
+            }
+            CouncilAction::RevokeGrant(id) => {
+                Self::clawback_grant(e.clone(), id);
+            }
+            CouncilAction::CancelAdminProposal => Self::withdraw_admin_proposal(e.clone()),
+            CouncilAction::GrantRole(role, account) => Self::assign_role(e.clone(), role, account),
+            CouncilAction::RevokeRole(role, account) => {
+                Self::unassign_role(e.clone(), role, account);
+            }
+            CouncilAction::SetJoinMode(mode) => {
+                Self::store_join_mode(e.clone(), mode);
+            }
+            CouncilAction::AddVoucherKey(key) => Self::store_voucher_key(e.clone(), key),
+            CouncilAction::SetReferralPolicy(policy) => {
+                Self::store_referral_policy(e.clone(), policy);
+            }
+            CouncilAction::RemoveTier(name) => {
+                Self::drop_tier(e.clone(), name);
+            }
+            CouncilAction::SetDisputeWindow(window) => Self::store_dispute_window(e.clone(), window),
+            CouncilAction::SetRefundPolicy(policy) => {
+                Self::store_refund_policy(e.clone(), policy);
+            }
+            CouncilAction::SetStaking(staking) => Self::store_staking(e.clone(), staking),
+        }
+
+        e.events().publish((COUNCIL, symbol_short!("execute")), id);
+    }
+
+    pub fn proposal(e: Env, id: u32) -> Option<Proposal> {
+        e.storage().persistent().get(&Datakey::Proposal(id))
+    }
+
+    pub fn withdraw_amount(e: Env, caller: Address, to: Address, amount: i128) -> i128 {
+        require_role(&e, &caller, &TREASURER);
+        require_no_council(&e);
+        Self::pay_out(e, to, amount)
+    }
+
+    fn pay_out(e: Env, to: Address, amount: i128) -> i128 {
+        if amount <= 0 {
+            panic!("amount must be positive");
+        }
+
+        let collective = read_collective(&e);
+        let client = token::Client::new(&e, &collective.pay_token);
+
+        if available_balance(&e, &client) < amount {
+            panic!("not enough collected to withdraw");
+        }
+
+        client.transfer(&e.current_contract_address(), &to, &amount);
+        record_withdrawal(&e, &to, amount);
+        e.events().publish((PAYOUT, symbol_short!("withdraw")), (to, amount));
+
+        amount
+    }
+
+    pub fn set_splits(e: Env, caller: Address, splits: Vec<Split>) {
+        require_role(&e, &caller, &TREASURER);
+        require_no_council(&e);
+        Self::store_splits(e, splits);
+    }
+
+    fn store_splits(e: Env, splits: Vec<Split>) {
+        let mut total: u32 = 0;
+
+        for split in splits.iter() {
+            if split.bps == 0 {
+                panic!("split must be positive");
+            }
+            total += split.bps;
+        }
+
+        if total != MAX_BPS {
+            panic!("splits must total 10000 bps");
+        }
+
+        e.storage().persistent().set(&Datakey::Splits, &splits);
+        e.events().publish((PAYOUT, symbol_short!("splits")), splits.len());
+    }
+
+    pub fn splits(e: Env) -> Vec<Split> {
+        e.storage().persistent().get(&Datakey::Splits).unwrap_or(Vec::new(&e))
+    }
+
+    pub fn distribute(e: Env, caller: Address) -> i128 {
+        require_role(&e, &caller, &TREASURER);
+        require_no_council(&e);
+        Self::distribute_splits(e)
+    }
+
+    fn distribute_splits(e: Env) -> i128 {
+        let splits: Vec<Split> = e.storage().persistent().get(&Datakey::Splits).expect("no revenue splits");
+        let collective = read_collective(&e);
+        let client = token::Client::new(&e, &collective.pay_token);
+        let available = available_balance(&e, &client);
+        let mut distributed: i128 = 0;
+
+        if available <= 0 {
+            panic!("nothing to distribute");
+        }
+
+        // rounding dust stays in the treasury for the next distribution
+        for split in splits.iter() {
+            let amount = available * split.bps as i128 / MAX_BPS as i128;
+
+            if amount > 0 {
+                client.transfer(&e.current_contract_address(), &split.recipient, &amount);
+                record_withdrawal(&e, &split.recipient, amount);
+                e.events().publish((PAYOUT, symbol_short!("split")), (split.recipient, amount));
+                distributed += amount;
+            }
+        }
+
+        distributed
+    }
+
+    /// Sets aside `amount` of the treasury for OPUS holders and returns the
+    /// dividend epoch they claim it under.
+    pub fn declare_dividend(e: Env, caller: Address, amount: i128) -> u32 {
+        require_role(&e, &caller, &TREASURER);
+        require_no_council(&e);
+        Self::earmark_dividend(e, amount)
+    }
+
+    fn earmark_dividend(e: Env, amount: i128) -> u32 {
+        if !Self::is_launched(e.clone()) {
+            panic!("opus not launched");
+        }
+
+        if amount <= 0 {
+            panic!("amount must be positive");
+        }
+
+        let collective = read_collective(&e);
+        let client = token::Client::new(&e, &collective.pay_token);
+
+        if available_balance(&e, &client) < amount {
+            panic!("not enough collected to withdraw");
+        }
+
+        // snapshot the last closed ledger so nobody can buy in once the
+        // dividend is announced
+        let snapshot = e.ledger().sequence().checked_sub(1).expect("no closed ledger");
+        let opus_address: Address = e.storage().instance().get(&OPUS).unwrap();
+        let opus = opus_token::Client::new(&e, &opus_address);
+        let supply = opus.total_supply_at(&snapshot) - opus.balance_at(&e.current_contract_address(), &snapshot);
+
+        if supply <= 0 {
+            panic!("no opus in circulation");
+        }
+
+        let epoch: u32 = e.storage().persistent().get(&Datakey::DividendCount).unwrap_or(0);
+        let deadline = e.ledger().sequence() + DIVIDEND_WINDOW;
+        let staking = Self::staking(e.clone());
+        let dividend = Dividend { amount, snapshot, supply, claimed: 0, deadline, reclaimed: 0, staking };
+
+        reserve(&e, amount);
+        e.storage().persistent().set(&Datakey::Dividend(epoch), &dividend);
+        e.storage().persistent().set(&Datakey::DividendCount, &(epoch + 1));
+        e.events().publish((DIVIDEND, symbol_short!("declare")), (epoch, amount, snapshot));
+
+        epoch
+    }
+
+    pub fn claim_dividend(e: Env, caller: Address, epoch: u32) -> i128 {
+        caller.require_auth();
+
+        let mut dividend: Dividend = e.storage().persistent().get(&Datakey::Dividend(epoch)).expect("no dividend");
+        let claim = Datakey::DividendClaim(epoch, caller.clone());
+
+        if e.storage().persistent().has(&claim) {
+            panic!("dividend already claimed");
+        }
+
+        if e.ledger().sequence() > dividend.deadline {
+            panic!("claim window closed");
+        }
+
+        let opus_address: Address = e.storage().instance().get(&OPUS).unwrap();
+        let mut balance = opus_token::Client::new(&e, &opus_address).balance_at(&caller, &dividend.snapshot);
+
+        // staked OPUS is held by the staking contract but claimed by its stakers
+        if let Some(staking) = &dividend.staking {
+            if caller == *staking {
+                panic!("staking contract cannot claim");
+            }
+            balance += opus_staking::Client::new(&e, staking).staked_at(&caller, &dividend.snapshot);
+        }
+
+        let share = dividend.amount.checked_mul(balance).expect("amount overflow") / dividend.supply;
+
+        if share <= 0 {
+            panic!("nothing to claim");
+        }
+
+        if dividend.claimed + share > dividend.amount - dividend.reclaimed {
+            panic!("claim exceeds dividend");
+        }
+
+        dividend.claimed += share;
+        e.storage().persistent().set(&claim, &true);
+        e.storage().persistent().set(&Datakey::Dividend(epoch), &dividend);
+
+        let collective = read_collective(&e);
+        let client = token::Client::new(&e, &collective.pay_token);
+        release(&e, share);
+        client.transfer(&e.current_contract_address(), &caller, &share);
+        record_outflow(&e, &OUTFLOW_DIVIDEND, share);
+        e.events().publish((DIVIDEND, symbol_short!("claim")), (epoch, caller, share));
+
+        share
+    }
+
+    /// Returns the unclaimed part of a dividend to the treasury once its
+    /// claim window has closed. OPUS held by contracts that cannot claim,
+    /// such as pools, counts towards `supply`, so some of every dividend is
+    /// always left over. Anyone may reclaim.
+    pub fn reclaim_dividend(e: Env, epoch: u32) -> i128 {
+        let mut dividend: Dividend = e.storage().persistent().get(&Datakey::Dividend(epoch)).expect("no dividend");
+
+        if e.ledger().sequence() <= dividend.deadline {
+            panic!("claim window still open");
+        }
+
+        let remaining = dividend.amount - dividend.claimed - dividend.reclaimed;
+
+        if remaining <= 0 {
+            panic!("nothing to reclaim");
+        }
+
+        dividend.reclaimed += remaining;
+        e.storage().persistent().set(&Datakey::Dividend(epoch), &dividend);
+        release(&e, remaining);
+        e.events().publish((DIVIDEND, symbol_short!("reclaim")), (epoch, remaining));
+
+        remaining
+    }
+
+    pub fn dividend(e: Env, epoch: u32) -> Option<Dividend> {
+        e.storage().persistent().get(&Datakey::Dividend(epoch))
+    }
+
+    pub fn dividend_claimed(e: Env, epoch: u32, account: Address) -> bool {
+        e.storage().persistent().has(&Datakey::DividendClaim(epoch, account))
+    }
+
+    pub fn symbol(e: Env) -> Symbol {
+        let collective = read_collective(&e);
+        collective.symbol
+    }
+
+    pub fn join_fee(e: Env) -> i128 {
+        let collective = read_collective(&e);
+        collective.join_fee
+    }
+
+    pub fn mint_fee(e: Env) -> i128 {
+        let collective = read_collective(&e);
+        collective.mint_fee
+    }
+
+    pub fn opus_reward(e: Env) -> i128 {
+        let collective = read_collective(&e);
+        collective.opus_reward
+    }
+
+    pub fn member_paid(e: Env, caller: Address) -> i128 {
+        caller.require_auth();
+        read_member(&e, &caller).map(|member| member.paid).unwrap_or(0)
+    }
+
+    pub fn opus_address(e: Env)-> Address {
+        let address: Address = e.storage().instance().get::<Symbol, Address>(&OPUS).expect("network not initialized");
+        address
+    }
+
+    pub fn is_member(e: Env, caller: Address) -> bool {
+        let this_contract = e.current_contract_address();
+        let admin: Address = e.storage().instance().get(&ADMIN).unwrap();
+
+        if caller == admin || caller == this_contract {
+            return true;
+        }
+
+        if is_banned(&e, &caller) || is_suspended(&e, &caller) {
+            return false;
+        }
+
+        match read_member(&e, &caller) {
+            Some(member) => member_status(&e, &member) != MemberStatus::Expired,
+            None => false,
+        }
+    }
+
+    pub fn remove(e:Env, caller: Address, member: Address)-> bool{
+        require_role(&e, &caller, &MODERATOR);
+        require_no_council(&e);
+        Self::remove_member(e, member)
+    }
+
+    fn remove_member(e: Env, member: Address) -> bool {
+        if !e.storage().persistent().has(&Datakey::Member(member.clone())) {
+            return false;
+        }
+
+        drop_member(&e, &member);
+        e.events().publish((REMOVE, symbol_short!("member")), member);
+        true
+    }
+
+    pub fn ban(e: Env, caller: Address, member: Address, reason: String, expires: u64) -> Ban {
+        require_role(&e, &caller, &MODERATOR);
+        require_no_council(&e);
+        Self::ban_member(e, member, reason, expires)
+    }
+
+    fn ban_member(e: Env, member: Address, reason: String, expires: u64) -> Ban {
+        let admin: Address = e.storage().instance().get(&ADMIN).unwrap();
+
+        if member == admin {
+            panic!("cannot ban admin");
+        }
+
+        if e.storage().persistent().has(&Datakey::Member(member.clone())) {
+            drop_member(&e, &member);
+        }
+
+        let ban = Ban {
+            reason: reason.clone(),
+            banned_at: e.ledger().timestamp(),
+            expires,
+            appeal: None,
+        };
+
+        e.storage().persistent().set(&Datakey::Ban(member.clone()), &ban);
+        e.events().publish((BAN, symbol_short!("member")), (member, reason, expires));
+
+        ban
+    }
+
+    pub fn unban(e: Env, caller: Address, member: Address) -> bool {
+        require_role(&e, &caller, &MODERATOR);
+
+        if !e.storage().persistent().has(&Datakey::Ban(member.clone())) {
+            return false;
+        }
+
+        e.storage().persistent().remove(&Datakey::Ban(member.clone()));
+        e.events().publish((BAN, symbol_short!("lift")), member);
+        true
+    }
+
+    pub fn appeal(e: Env, caller: Address, appeal: String) {
+
+        caller.require_auth();
+        let mut ban: Ban = e.storage().persistent().get(&Datakey::Ban(caller.clone())).expect("not banned");
+
+        if ban.appeal.is_some() {
+            panic!("appeal already pending");
+        }
+
+        ban.appeal = Some(appeal.clone());
+        e.storage().persistent().set(&Datakey::Ban(caller.clone()), &ban);
+        e.events().publish((BAN, symbol_short!("appeal")), (caller, appeal));
+    }
+
+    pub fn is_banned(e: Env, caller: Address) -> bool {
+        is_banned(&e, &caller)
+    }
+
+    pub fn ban_info(e: Env, caller: Address) -> Option<Ban> {
+        e.storage().persistent().get(&Datakey::Ban(caller))
+    }
+
+    pub fn suspend(e: Env, caller: Address, member: Address, until_ledger: u32, reason: String) -> Suspension {
+        require_role(&e, &caller, &MODERATOR);
+
+        if read_member(&e, &member).is_none() {
+            panic!("not a member");
+        }
+
+        if until_ledger <= e.ledger().sequence() {
+            panic!("suspension must end in the future");
+        }
+
+        let suspension = Suspension {
+            since: e.ledger().sequence(),
+            until: until_ledger,
+            reason: reason.clone(),
+        };
+
+        e.storage().persistent().set(&Datakey::Suspension(member.clone()), &suspension);
+        e.events().publish((SUSPEND, symbol_short!("member")), (member, until_ledger, reason));
+
+        suspension
+    }
+
+    pub fn reinstate(e: Env, caller: Address, member: Address) -> bool {
+        require_role(&e, &caller, &MODERATOR);
+
+        if !e.storage().persistent().has(&Datakey::Suspension(member.clone())) {
+            return false;
+        }
+
+        e.storage().persistent().remove(&Datakey::Suspension(member.clone()));
+        e.events().publish((SUSPEND, symbol_short!("lift")), member);
+        true
+    }
+
+    pub fn is_suspended(e: Env, caller: Address) -> bool {
+        is_suspended(&e, &caller)
+    }
+
+    pub fn suspension(e: Env, caller: Address) -> Option<Suspension> {
+        e.storage().persistent().get(&Datakey::Suspension(caller))
+    }
+
+    pub fn leave(e: Env, caller: Address) -> i128 {
+
+        caller.require_auth();
+        let member = read_member(&e, &caller).expect("not a member");
+        let policy = read_refund_policy(&e);
+        let refund = refund_amount(&e, &member, &policy);
+
+        if refund > 0 {
+            let collective = read_collective(&e);
+            let client = token::Client::new(&e, &collective.pay_token);
+
+            if available_balance(&e, &client) < refund {
+                panic!("not enough to refund");
+            }
+
+            client.transfer(&e.current_contract_address(), &caller, &refund);
+            record_outflow(&e, &OUTFLOW_REFUND, refund);
+        }
+
+        drop_member(&e, &caller);
+        e.events().publish((LEAVE, symbol_short!("member")), (caller, refund));
+
+        refund
+    }
+
+    pub fn set_refund_policy(e: Env, caller: Address, percent: u32, window: u64) -> RefundPolicy {
+        require_role(&e, &caller, &FEE_MANAGER);
+        require_no_council(&e);
+        Self::store_refund_policy(e, RefundPolicy { percent, window })
+    }
+
+    // The first policy applies straight away; later changes give members
+    // FEE_NOTICE ledgers of warning before their refund changes.
+    fn store_refund_policy(e: Env, policy: RefundPolicy) -> RefundPolicy {
+        if policy.percent > 100 {
+            panic!("refund must not exceed 100");
+        }
+
+        if e.storage().persistent().has(&Datakey::RefundPolicy) {
+            let current = settle_refund_policy(&e);
+            let schedule = RefundSchedule { policy: policy.clone(), effective: e.ledger().sequence() + FEE_NOTICE };
+
+            e.storage().persistent().set(&Datakey::RefundSchedule, &schedule);
+            e.events().publish((FEES, symbol_short!("refund")), (current, schedule));
+            return policy;
+        }
+
+        e.storage().persistent().set(&Datakey::RefundPolicy, &policy);
+        policy
+    }
+
+    pub fn refund_policy(e: Env) -> RefundPolicy {
+        read_refund_policy(&e)
+    }
+
+    /// Refund policy that has been scheduled but is not yet in force.
+    pub fn pending_refund_policy(e: Env) -> Option<RefundSchedule> {
+        e.storage().persistent().get::<_, RefundSchedule>(&Datakey::RefundSchedule)
+            .filter(|schedule| schedule.effective > e.ledger().sequence())
+    }
+
+    pub fn leave_refund(e: Env, caller: Address) -> i128 {
+        let member = read_member(&e, &caller).expect("not a member");
+        refund_amount(&e, &member, &read_refund_policy(&e))
+    }
+
+    pub fn deploy_node_token(e:Env, caller: Address, name: String, descriptor: String)-> Address{
+
+        caller.require_auth();
+
+        if !Self::is_member(e.clone(), caller.clone()) {
+            panic!("unauthorized");
+        }
+
+        require_action(&caller_tier(&e, &caller), ACTION_NODE);
+
+        let ledger = e.ledger();
+        let symbol = String::from_val(&e, &"HVYMNODE");
+        let b: [u8; 32] = hash_string(&e, &name).into();
+        let node_id = String::from_bytes(&e, &b);
+        let established = ledger.timestamp();
+        let wasm_hash = e.deployer().upload_contract_wasm(pintheon_node_token::WASM);
+        let str_addr = Address::to_string(&caller);
+        let salt = hash_string(&e, &str_addr);
+        let constructor_args: Vec<Val> = (caller.clone(), 0u32, name.clone(), symbol.clone(), node_id.clone(), descriptor.clone(), established.clone()).into_val(&e);
+
+        let contract_id = Self::deploy_contract(e.clone(), caller.clone(), wasm_hash.clone(), salt.clone(), constructor_args.clone());
+        let token = pintheon_node_token::Client::new(&e, &contract_id);
+        token.mint(&caller, &1);
+
+        add_profile_token(&e, &caller, &NODE_TOKEN, &contract_id);
+
+        contract_id
+    }
+
+    pub fn deploy_ipfs_token(e:Env, caller: Address, name: String, ipfs_hash: String, file_type: String, gateways: String, _ipns_hash: Option<String>)-> Address{
+
+        if Self::is_launched(e.clone()) == false {
+            panic!("network not up");
+        }
+
+        caller.require_auth();
+
+        if !Self::is_member(e.clone(), caller.clone()) {
+            panic!("unauthorized");
+        }
+        
+        let collective = read_collective(&e);
+        let tier = caller_tier(&e, &caller);
+        require_action(&tier, ACTION_FILE);
+
+        let client = token::Client::new(&e, &collective.pay_token);
+        let balance = client.balance(&caller);
+        let mint_fee = member_mint_fee(&e, &caller, &collective, &tier);
+
+        if balance < mint_fee {
+            panic!("not enough to cover fee");
+        }
+
+        client.transfer(&caller, &e.current_contract_address(), &mint_fee);
+
+        // the opus reward is held with the fee, so a rejected upload earns nothing
+        let reward = collective.opus_reward.checked_mul(tier.reward_multiplier as i128).expect("amount overflow") / 100;
+        hold_mint_fee(&e, &INCOME_MINT, &caller, mint_fee, reward, &ipfs_hash);
+
+        let ledger = e.ledger();
+        let symbol = String::from_val(&e, &"HVYMFILE");
+        let published = ledger.timestamp();
+        let wasm_hash = e.deployer().upload_contract_wasm(pintheon_ipfs_token::WASM);
+        let salt = hash_string(&e, &ipfs_hash);
+        let constructor_args: Vec<Val> = (caller.clone(), 0u32, name.clone(), symbol.clone(), ipfs_hash.clone(), file_type.clone(), published.clone(), gateways.clone(), _ipns_hash.clone()).into_val(&e);
+
+        let contract_id = Self::deploy_contract(e.clone(), caller.clone(), wasm_hash.clone(), salt.clone(), constructor_args.clone());
+
+        add_profile_token(&e, &caller, &FILE_TOKEN, &contract_id);
+
+        contract_id
+    }
+
+    pub fn publish_file(e: Env, caller: Address, ipfs_hash: String) {
+
+        caller.require_auth();
+        require_not_banned(&e, &caller);
+        require_not_suspended(&e, &caller);
+        let collective = read_collective(&e);
+        let tier = caller_tier(&e, &caller);
+        require_action(&tier, ACTION_PUBLISH);
+
+        let client = token::Client::new(&e, &collective.pay_token);
+        let balance = client.balance(&caller);
+        let mint_fee = member_mint_fee(&e, &caller, &collective, &tier);
+
+        if balance < mint_fee {
+            panic!("not enough to cover fee");
+        }
+
+        client.transfer(&caller, &e.current_contract_address(), &mint_fee);
+        hold_mint_fee(&e, &INCOME_PUBLISH, &caller, mint_fee, 0, &ipfs_hash);
+
+        e.events().publish((PUBLISH, symbol_short!("file")), (caller, ipfs_hash));
+    }
+
+    pub fn publish_encrypted_share(e: Env, caller: Address, recipient: Address, ipfs_hash: String) {
+
+        caller.require_auth();
+        require_not_banned(&e, &caller);
+        require_not_suspended(&e, &caller);
+        let collective = read_collective(&e);
+        let tier = caller_tier(&e, &caller);
+        require_action(&tier, ACTION_PUBLISH);
+
+        let client = token::Client::new(&e, &collective.pay_token);
+        let balance = client.balance(&caller);
+        let mint_fee = member_mint_fee(&e, &caller, &collective, &tier);
+
+        if balance < mint_fee {
+            panic!("not enough to cover fee");
+        }
+
+        client.transfer(&caller, &e.current_contract_address(), &mint_fee);
+        hold_mint_fee(&e, &INCOME_SHARE, &caller, mint_fee, 0, &ipfs_hash);
+
+        e.events().publish((PUBLISH, symbol_short!("encrypted")), (caller, recipient, ipfs_hash));
+    }
+
+    /// How long, in seconds, mint fees stay in escrow before they can be
+    /// settled into the treasury. 0 sends fees to the treasury straight away.
+    pub fn set_dispute_window(e: Env, caller: Address, window: u64) {
+        require_role(&e, &caller, &FEE_MANAGER);
+        require_no_council(&e);
+        Self::store_dispute_window(e, window);
+    }
+
+    fn store_dispute_window(e: Env, window: u64) {
+        e.storage().persistent().set(&Datakey::DisputeWindow, &window);
+    }
+
+    pub fn dispute_window(e: Env) -> u64 {
+        e.storage().persistent().get(&Datakey::DisputeWindow).unwrap_or(0)
+    }
+
+    pub fn escrow(e: Env, escrow_id: u32) -> Option<Escrow> {
+        e.storage().persistent().get(&Datakey::Escrow(escrow_id))
+    }
+
+    /// Rejects a publication while its fee is still in escrow, either
+    /// refunding the publisher or confiscating the fee into the treasury.
+    /// The opus reward held with the fee is forfeited either way.
+    pub fn reject_publication(e: Env, caller: Address, escrow_id: u32, refund: bool) -> i128 {
+        require_role(&e, &caller, &MODERATOR);
+        let escrow = take_escrow(&e, escrow_id);
+
+        if e.ledger().timestamp() >= escrow.release_at {
+            panic!("dispute window closed");
+        }
+
+        // a refunded fee is booked as collected and then refunded, so the
+        // treasury report still adds up
+        record_income(&e, &escrow.category, &escrow.payer, escrow.amount);
+
+        if refund {
+            let collective = read_collective(&e);
+            let client = token::Client::new(&e, &collective.pay_token);
+            client.transfer(&e.current_contract_address(), &escrow.payer, &escrow.amount);
+            record_outflow(&e, &OUTFLOW_REFUND, escrow.amount);
+        }
+
+        e.events().publish((PUBLISH, symbol_short!("reject")), (escrow_id, escrow.ipfs_hash, refund));
+        escrow.amount
+    }
+
+    /// Moves an undisputed fee into the treasury and pays out the publisher's
+    /// opus reward once its window has passed. Anyone may settle.
+    pub fn settle(e: Env, escrow_id: u32) -> i128 {
+        let escrow = take_escrow(&e, escrow_id);
+
+        if e.ledger().timestamp() < escrow.release_at {
+            panic!("dispute window still open");
+        }
+
+        record_income(&e, &escrow.category, &escrow.payer, escrow.amount);
+        pay_reward(&e, &escrow.payer, escrow.reward);
+        e.events().publish((PUBLISH, symbol_short!("settle")), (escrow_id, escrow.amount));
+        escrow.amount
+    }
+
+    /// Deploys OPUS with a hard supply `cap`. Rewards are limited to
+    /// `epoch_emission` per `epoch_length` ledgers, halving each epoch.
+    pub fn launch_opus(e:Env, caller: Address, initial_alloc: i128, cap: i128, epoch_length: u32, epoch_emission: i128)-> Address{
+        require_role(&e, &caller, &LAUNCHER);
+        require_no_council(&e);
+        Self::deploy_opus(e, initial_alloc, cap, epoch_length, epoch_emission)
+    }
+
+    fn deploy_opus(e: Env, initial_alloc: i128, cap: i128, epoch_length: u32, epoch_emission: i128) -> Address {
+        if Self::is_launched(e.clone()) {
+            panic!("opus already up");
+        }
+
+        require_valid_amount(initial_alloc);
+        require_valid_amount(epoch_emission);
+
+        let admin: Address = e.storage().instance().get(&ADMIN).unwrap();
+        let wasm_hash = e.deployer().upload_contract_wasm(opus_token::WASM);
+        let str_addr = Address::to_string(&admin);
+        let salt = hash_string(&e, &str_addr);
+        let this_contract = &e.current_contract_address();
+        let constructor_args: Vec<Val> = (this_contract.clone(), this_contract.clone(), initial_alloc, cap, epoch_length, epoch_emission).into_val(&e);
+
+        let contract_id = Self::deploy_contract(e.clone(), this_contract.clone(), wasm_hash.clone(), salt.clone(), constructor_args.clone());
+        e.storage().instance().set(&OPUS, &contract_id);
+
+        contract_id
+
+    }
+
+    /// OPUS that can still be minted as rewards this epoch.
+    pub fn opus_budget(e: Env) -> i128 {
+        match e.storage().instance().get::<_, Address>(&OPUS) {
+            Some(opus_address) => opus_token::Client::new(&e, &opus_address).remaining_emission(),
+            None => 0,
+        }
+    }
+
+    /// Locks `amount` of the collective's OPUS for `beneficiary`, vesting
+    /// linearly over `duration` seconds from now with nothing released
+    /// before `cliff` seconds have passed.
+    pub fn grant_vesting(e: Env, caller: Address, beneficiary: Address, amount: i128, cliff: u64, duration: u64, revocable: bool) -> u32 {
+        require_role(&e, &caller, &LAUNCHER);
+        require_no_council(&e);
+        Self::allocate_grant(e, beneficiary, amount, cliff, duration, revocable)
+    }
+
+    fn allocate_grant(e: Env, beneficiary: Address, amount: i128, cliff: u64, duration: u64, revocable: bool) -> u32 {
+        if !Self::is_launched(e.clone()) {
+            panic!("opus not launched");
+        }
+
+        if amount <= 0 {
+            panic!("amount must be positive");
+        }
+
+        if duration == 0 || cliff > duration {
+            panic!("invalid vesting schedule");
+        }
+
+        let opus_address: Address = e.storage().instance().get(&OPUS).unwrap();
+        let held = opus_token::Client::new(&e, &opus_address).balance(&e.current_contract_address());
+        let granted = Self::granted(e.clone());
+
+        if held - granted < amount {
+            panic!("not enough unallocated opus");
+        }
+
+        let start = e.ledger().timestamp();
+        let id: u32 = e.storage().persistent().get(&Datakey::GrantCount).unwrap_or(0);
+        let grant = Grant {
+            beneficiary: beneficiary.clone(),
+            amount,
+            released: 0,
+            start,
+            cliff: start + cliff,
+            end: start + duration,
+            revocable,
+            revoked: false,
+        };
+
+        e.storage().persistent().set(&Datakey::Grant(id), &grant);
+        e.storage().persistent().set(&Datakey::GrantCount, &(id + 1));
+        e.storage().persistent().set(&Datakey::Granted, &(granted + amount));
+        e.events().publish((VESTING, symbol_short!("grant")), (id, beneficiary, amount, grant.end));
+
+        id
+    }
+
+    /// Pays out whatever part of grant `id` has vested and not yet been
+    /// released.
+    pub fn release(e: Env, beneficiary: Address, id: u32) -> i128 {
+        beneficiary.require_auth();
+
+        let mut grant = read_grant(&e, id);
+
+        if grant.beneficiary != beneficiary {
+            panic!("not the beneficiary");
+        }
+
+        let amount = vested_amount(&e, &grant) - grant.released;
+
+        if amount <= 0 {
+            panic!("nothing to release");
+        }
+
+        grant.released += amount;
+        e.storage().persistent().set(&Datakey::Grant(id), &grant);
+        e.storage().persistent().set(&Datakey::Granted, &(Self::granted(e.clone()) - amount));
+
+        let opus_address: Address = e.storage().instance().get(&OPUS).unwrap();
+        opus_token::Client::new(&e, &opus_address).transfer(&e.current_contract_address(), &beneficiary, &amount);
+        e.events().publish((VESTING, symbol_short!("release")), (id, beneficiary, amount));
+
+        amount
+    }
+
+    /// Claws back the unvested part of a revocable grant. What has already
+    /// vested stays releasable by the beneficiary.
+    pub fn revoke_grant(e: Env, caller: Address, id: u32) -> i128 {
+        require_role(&e, &caller, &LAUNCHER);
+        require_no_council(&e);
+        Self::clawback_grant(e, id)
+    }
+
+    fn clawback_grant(e: Env, id: u32) -> i128 {
+        let mut grant = read_grant(&e, id);
+
+        if !grant.revocable {
+            panic!("grant not revocable");
+        }
+
+        if grant.revoked {
+            panic!("grant already revoked");
+        }
+
+        let vested = vested_amount(&e, &grant);
+        let clawback = grant.amount - vested;
+
+        grant.amount = vested;
+        grant.revoked = true;
+        e.storage().persistent().set(&Datakey::Grant(id), &grant);
+        e.storage().persistent().set(&Datakey::Granted, &(Self::granted(e.clone()) - clawback));
+        e.events().publish((VESTING, symbol_short!("revoke")), (id, clawback));
+
+        clawback
+    }
+
+    pub fn grant(e: Env, id: u32) -> Option<Grant> {
+        e.storage().persistent().get(&Datakey::Grant(id))
+    }
+
+    pub fn grant_count(e: Env) -> u32 {
+        e.storage().persistent().get(&Datakey::GrantCount).unwrap_or(0)
+    }
+
+    /// OPUS of grant `id` that has vested but not been released.
+    pub fn releasable(e: Env, id: u32) -> i128 {
+        let grant = read_grant(&e, id);
+        vested_amount(&e, &grant) - grant.released
+    }
+
+    /// OPUS the collective holds on behalf of grants, vested or not, that
+    /// has not been released yet.
+    pub fn granted(e: Env) -> i128 {
+        e.storage().persistent().get(&Datakey::Granted).unwrap_or(0)
+    }
+
+    /// Points the collective at the OPUS staking contract it reads member
+    /// stakes from.
+    pub fn set_staking(e: Env, staking: Address) {
+        let admin: Address = e.storage().instance().get(&ADMIN).unwrap();
         admin.require_auth();
-        let  mut collective: Collective = storage_g(e.clone(), Kind::Permanent, Datakey::Collective).expect("cound find collective");
-        collective.opus_reward = new_reward;
+        require_no_council(&e);
+        Self::store_staking(e, staking);
+    }
+
+    fn store_staking(e: Env, staking: Address) {
+        e.storage().persistent().set(&Datakey::Staking, &staking);
+    }
+
+    pub fn staking(e: Env) -> Option<Address> {
+        e.storage().persistent().get(&Datakey::Staking)
+    }
+
+    /// OPUS `member` has locked in the staking contract, or 0 if none is set.
+    pub fn member_stake(e: Env, member: Address) -> i128 {
+        match Self::staking(e.clone()) {
+            Some(staking) => opus_staking::Client::new(&e, &staking).staked(&member),
+            None => 0,
+        }
+    }
+
+    pub fn is_launched(e: Env) -> bool {
+        let launched = if e.storage().instance().get::<_, Address>(&OPUS).is_some() { true } else { false }; 
+
+        launched
+    }
+
+    pub fn update_join_fee(e: Env, caller: Address, new_fee: i128) -> i128 {
+        require_role(&e, &caller, &FEE_MANAGER);
+        require_no_council(&e);
+        Self::set_join_fee(e, new_fee)
+    }
+
+    fn set_join_fee(e: Env, new_fee: i128) -> i128 {
+        let mut schedule = next_fees(&e);
+        schedule.join_fee = new_fee;
+
+        Self::queue_fees(e, schedule);
+        new_fee
+    }
+
+    pub fn update_mint_fee(e: Env, caller: Address, new_fee: i128) -> i128 {
+        require_role(&e, &caller, &FEE_MANAGER);
+        require_no_council(&e);
+        Self::set_mint_fee(e, new_fee)
+    }
+
+    fn set_mint_fee(e: Env, new_fee: i128) -> i128 {
+        let mut schedule = next_fees(&e);
+        schedule.mint_fee = new_fee;
+
+        Self::queue_fees(e, schedule);
+        new_fee
+    }
+
+    pub fn update_opus_reward(e: Env, caller: Address, new_reward: i128) -> i128 {
+        require_role(&e, &caller, &FEE_MANAGER);
+        require_no_council(&e);
+        Self::set_opus_reward(e, new_reward)
+    }
+
+    fn set_opus_reward(e: Env, new_reward: i128) -> i128 {
+        let mut schedule = next_fees(&e);
+        schedule.opus_reward = new_reward;
+
+        Self::queue_fees(e, schedule);
+        new_reward
+    }
+
+    /// Queues a new fee set that takes over at `effective` ledger, replacing
+    /// any schedule that is still pending. `effective` must be at least
+    /// FEE_NOTICE ledgers away.
+    pub fn schedule_fees(e: Env, caller: Address, join_fee: i128, mint_fee: i128, opus_reward: i128, effective: u32) {
+        require_role(&e, &caller, &FEE_MANAGER);
+        require_no_council(&e);
+        Self::queue_fees(e, FeeSchedule { join_fee, mint_fee, opus_reward, effective });
+    }
+
+    fn queue_fees(e: Env, schedule: FeeSchedule) {
+        if schedule.effective < e.ledger().sequence() + FEE_NOTICE {
+            panic!("fee changes need notice");
+        }
+
+        require_valid_amount(schedule.join_fee);
+        require_valid_amount(schedule.mint_fee);
+        require_valid_amount(schedule.opus_reward);
+
+        let collective = settle_fees(&e);
+        let current = (collective.join_fee, collective.mint_fee, collective.opus_reward);
+
+        e.storage().persistent().set(&Datakey::FeeSchedule, &schedule);
+        e.events().publish((FEES, symbol_short!("schedule")), (current, schedule));
+    }
+
+    /// Fee set that has been scheduled but is not yet in force.
+    pub fn pending_fees(e: Env) -> Option<FeeSchedule> {
+        read_fee_schedule(&e).filter(|schedule| schedule.effective > e.ledger().sequence())
+    }
+
+    fn deploy_contract(
+        e: Env,
+        caller: Address,
+        wasm_hash: BytesN<32>,
+        salt: BytesN<32>,
+        constructor_args: Vec<Val>,
+    ) -> Address {
+        
+        if !Self::is_member(e.clone(), caller.clone()) {
+            panic!("unauthorized");
+        }
+
+        let deployed_address = e
+            .deployer()
+            .with_address(e.current_contract_address(), salt)
+            .deploy_v2(wasm_hash, constructor_args);
+
+        deployed_address
+    }
+
+}
+
+fn hash_string(env: &Env, s: &String) -> BytesN<32> {
+    let len = s.len() as usize;
+    let mut bytes = [0u8; 100];
+    let bytes = &mut bytes[0..len];
+    s.copy_into_slice(bytes);
+    let mut b = Bytes::new(env);
+    b.copy_from_slice(0, bytes);
+    env.crypto().sha256(&b).to_bytes()
+}
+
+fn read_member(e: &Env, caller: &Address) -> Option<Member> {
+    let val: Val = e.storage().persistent().get(&Datakey::Member(caller.clone()))?;
+
+    if let Ok(member) = Member::try_from_val(e, &val) {
+        return Some(member);
+    }
+
+    // members that joined before tiers existed were stored as the fee they paid
+    let paid = u32::try_from_val(e, &val).unwrap_or(0) as i128;
+    Some(Member { address: caller.clone(), paid, tier: DEFAULT_TIER, joined: 0, joined_ledger: 0, expires: 0 })
+}
+
+fn require_joinable(e: &Env, caller: &Address) {
+    if e.storage().persistent().has(&Datakey::Member(caller.clone())) {
+        panic!("already part of collective");
+    }
+
+    require_not_banned(e, caller);
+    require_not_suspended(e, caller);
+}
+
+// Creates the member record and profile once the join fee has been collected.
+fn enroll_member(e: &Env, caller: &Address, tier: &Tier, paid: i128, referrer: Option<Address>) {
+    let term = read_term(e);
+    let joined = e.ledger().timestamp();
+    let expires = if term.duration > 0 { joined + term.duration } else { 0 };
+
+    let member = Member {
+        address: caller.clone(),
+        paid,
+        tier: tier.name.clone(),
+        joined,
+        joined_ledger: e.ledger().sequence(),
+        expires,
+    };
+    add_member(e, &member);
+    e.storage().persistent().set(&Datakey::Profile(caller.clone()), &new_profile(e, joined));
+
+    e.events().publish((JOIN, symbol_short!("member")), (caller.clone(), paid));
+
+    if let Some(referrer) = referrer {
+        record_referral(e, &referrer, caller);
+    }
+}
 
-        if !validate_negative_amount(new_reward as i128) {
-            panic!("invalid amount, must be non-negative");
+fn read_join_mode(e: &Env) -> JoinMode {
+    e.storage().persistent().get(&Datakey::JoinMode).unwrap_or(JoinMode::Open)
+}
+
+fn require_open(e: &Env) {
+    if read_join_mode(e) == JoinMode::Gated {
+        panic!("application required");
+    }
+}
+
+// Returns an applicant's escrowed fee and discards the application.
+fn refund_application(e: &Env, applicant: &Address) -> i128 {
+    let application: Application = e.storage().persistent().get(&Datakey::Application(applicant.clone())).expect("no application");
+
+    if application.escrow > 0 {
+        let collective = read_collective(e);
+        let client = token::Client::new(e, &collective.pay_token);
+        release(e, application.escrow);
+        client.transfer(&e.current_contract_address(), applicant, &application.escrow);
+    }
+
+    e.storage().persistent().remove(&Datakey::Application(applicant.clone()));
+    application.escrow
+}
+
+fn ledger_total(e: &Env, key: Datakey) -> i128 {
+    e.storage().persistent().get(&key).unwrap_or(0)
+}
+
+fn add_to_ledger(e: &Env, key: Datakey, amount: i128) {
+    let total = ledger_total(e, key.clone());
+    e.storage().persistent().set(&key, &(total + amount));
+}
+
+fn record_income(e: &Env, category: &Symbol, from: &Address, amount: i128) {
+    if amount == 0 {
+        return;
+    }
+
+    add_to_ledger(e, Datakey::Income(category.clone()), amount);
+    add_to_ledger(e, Datakey::IncomeFrom(from.clone()), amount);
+}
+
+fn record_outflow(e: &Env, category: &Symbol, amount: i128) {
+    add_to_ledger(e, Datakey::Outflow(category.clone()), amount);
+}
+
+fn record_withdrawal(e: &Env, to: &Address, amount: i128) {
+    record_outflow(e, &OUTFLOW_WITHDRAW, amount);
+    add_to_ledger(e, Datakey::Withdrawn(to.clone()), amount);
+}
+
+fn deposit(e: &Env, from: &Address, amount: i128) {
+    if amount <= 0 {
+        panic!("amount must be positive");
+    }
+
+    let collective = read_collective(e);
+    let client = token::Client::new(e, &collective.pay_token);
+
+    if client.balance(from) < amount {
+        panic!("not enough to fund");
+    }
+
+    client.transfer(from, &e.current_contract_address(), &amount);
+}
+
+fn reserve(e: &Env, amount: i128) {
+    let reserved: i128 = e.storage().persistent().get(&Datakey::Reserved).unwrap_or(0);
+    e.storage().persistent().set(&Datakey::Reserved, &(reserved + amount));
+}
+
+fn release(e: &Env, amount: i128) {
+    let reserved: i128 = e.storage().persistent().get(&Datakey::Reserved).unwrap_or(0);
+    e.storage().persistent().set(&Datakey::Reserved, &(reserved - amount));
+}
+
+// Holds a publication's fee and opus reward until its dispute window closes,
+// or books them straight away when there is no window.
+fn hold_mint_fee(e: &Env, category: &Symbol, payer: &Address, amount: i128, reward: i128, ipfs_hash: &String) {
+    let window: u64 = e.storage().persistent().get(&Datakey::DisputeWindow).unwrap_or(0);
+
+    if window == 0 || (amount <= 0 && reward <= 0) {
+        record_income(e, category, payer, amount);
+        pay_reward(e, payer, reward);
+        return;
+    }
+
+    let id: u32 = e.storage().persistent().get(&Datakey::EscrowCount).unwrap_or(0);
+    let escrow = Escrow {
+        payer: payer.clone(),
+        amount,
+        reward,
+        category: category.clone(),
+        ipfs_hash: ipfs_hash.clone(),
+        release_at: e.ledger().timestamp() + window,
+    };
+
+    reserve(e, amount);
+    e.storage().persistent().set(&Datakey::Escrow(id), &escrow);
+    e.storage().persistent().set(&Datakey::EscrowCount, &(id + 1));
+    e.events().publish((PUBLISH, symbol_short!("escrow")), (id, payer.clone(), amount));
+}
+
+fn pay_reward(e: &Env, to: &Address, reward: i128) {
+    if reward > 0 {
+        let opus_address: Address = e.storage().instance().get(&OPUS).unwrap();
+        mint_opus(e, &opus_address, to, reward);
+    }
+}
+
+fn take_escrow(e: &Env, escrow_id: u32) -> Escrow {
+    let escrow: Escrow = e.storage().persistent().get(&Datakey::Escrow(escrow_id)).expect("no escrow");
+    e.storage().persistent().remove(&Datakey::Escrow(escrow_id));
+    release(e, escrow.amount);
+    escrow
+}
+
+// Treasury balance that is not set aside for sponsors, applicants, escrowed
+// mint fees or unclaimed dividends.
+fn available_balance(e: &Env, client: &token::Client) -> i128 {
+    let reserved: i128 = e.storage().persistent().get(&Datakey::Reserved).unwrap_or(0);
+    client.balance(&e.current_contract_address()) - reserved
+}
+
+fn add_member(e: &Env, member: &Member) {
+    let count: u32 = e.storage().persistent().get(&Datakey::MemberCount).unwrap_or(0);
+
+    e.storage().persistent().set(&Datakey::Member(member.address.clone()), member);
+    e.storage().persistent().set(&Datakey::MemberAt(count), &member.address);
+    e.storage().persistent().set(&Datakey::MemberIndex(member.address.clone()), &count);
+    e.storage().persistent().set(&Datakey::MemberCount, &(count + 1));
+}
+
+// Removes the member record and swaps the last indexed member into the freed
+// slot. Members stored before the index existed have no slot to free.
+fn drop_member(e: &Env, caller: &Address) {
+    e.storage().persistent().remove(&Datakey::Member(caller.clone()));
+    e.storage().persistent().remove(&Datakey::Profile(caller.clone()));
+
+    let index: Option<u32> = e.storage().persistent().get(&Datakey::MemberIndex(caller.clone()));
+
+    if let Some(index) = index {
+        let last = e.storage().persistent().get::<_, u32>(&Datakey::MemberCount).unwrap_or(1) - 1;
+
+        if index != last {
+            let moved: Address = e.storage().persistent().get(&Datakey::MemberAt(last)).unwrap();
+            e.storage().persistent().set(&Datakey::MemberAt(index), &moved);
+            e.storage().persistent().set(&Datakey::MemberIndex(moved), &index);
         }
 
-        storage_p(e, collective, Kind::Permanent, Datakey::Collective);
-        new_reward as i128
+        e.storage().persistent().remove(&Datakey::MemberAt(last));
+        e.storage().persistent().remove(&Datakey::MemberIndex(caller.clone()));
+        e.storage().persistent().set(&Datakey::MemberCount, &last);
     }
+}
 
-    fn deploy_contract(
-        e: Env,
-        caller: Address,
-        wasm_hash: BytesN<32>,
-        salt: BytesN<32>,
-        constructor_args: Vec<Val>,
-    ) -> Address {
-        
-        if !Self::is_member(e.clone(), caller.clone()) {
-            panic!("unauthorized");
+fn has_role(e: &Env, role: &Symbol, account: &Address) -> bool {
+    e.storage().persistent().get(&Datakey::Role(role.clone(), account.clone())).unwrap_or(false)
+}
+
+// The admin holds every role implicitly.
+fn require_role(e: &Env, caller: &Address, role: &Symbol) {
+    caller.require_auth();
+    let admin: Address = e.storage().instance().get(&ADMIN).unwrap();
+
+    if *caller != admin && !has_role(e, role, caller) {
+        panic!("unauthorized");
+    }
+}
+
+fn is_banned(e: &Env, caller: &Address) -> bool {
+    match e.storage().persistent().get::<_, Ban>(&Datakey::Ban(caller.clone())) {
+        Some(ban) => ban.expires == 0 || e.ledger().timestamp() < ban.expires,
+        None => false,
+    }
+}
+
+fn require_not_banned(e: &Env, caller: &Address) {
+    if is_banned(e, caller) {
+        panic!("banned");
+    }
+}
+
+// Suspensions lapse on their own once the ledger passes `until`.
+fn is_suspended(e: &Env, caller: &Address) -> bool {
+    match e.storage().persistent().get::<_, Suspension>(&Datakey::Suspension(caller.clone())) {
+        Some(suspension) => e.ledger().sequence() < suspension.until,
+        None => false,
+    }
+}
+
+fn require_not_suspended(e: &Env, caller: &Address) {
+    if is_suspended(e, caller) {
+        panic!("suspended");
+    }
+}
+
+fn new_profile(e: &Env, joined: u64) -> Profile {
+    Profile {
+        handle: String::from_str(e, ""),
+        avatar: String::from_str(e, ""),
+        bio: String::from_str(e, ""),
+        joined,
+    }
+}
+
+// Members who joined before profiles existed, and the admin, get an empty
+// profile on first use.
+fn read_profile(e: &Env, caller: &Address) -> Profile {
+    match e.storage().persistent().get(&Datakey::Profile(caller.clone())) {
+        Some(profile) => profile,
+        None => {
+            let joined = read_member(e, caller).map(|member| member.joined).unwrap_or(0);
+            new_profile(e, joined)
         }
+    }
+}
 
-        let deployed_address = e
-            .deployer()
-            .with_address(e.current_contract_address(), salt)
-            .deploy_v2(wasm_hash, constructor_args);
+fn profile_token_count(e: &Env, caller: &Address, kind: &Symbol) -> u32 {
+    e.storage().persistent().get(&Datakey::ProfileTokenCount(caller.clone(), kind.clone())).unwrap_or(0)
+}
 
-        deployed_address
+// Each token gets its own entry so a prolific member's profile never grows
+// past the ledger entry size limit.
+fn add_profile_token(e: &Env, caller: &Address, kind: &Symbol, token: &Address) {
+    let count = profile_token_count(e, caller, kind);
+
+    e.storage().persistent().set(&Datakey::ProfileToken(caller.clone(), kind.clone(), count), token);
+    e.storage().persistent().set(&Datakey::ProfileTokenCount(caller.clone(), kind.clone()), &(count + 1));
+}
+
+fn read_profile_tokens(e: &Env, caller: &Address, kind: &Symbol, start: u32, limit: u32) -> Vec<Address> {
+    let count = profile_token_count(e, caller, kind);
+    let end = count.min(start.saturating_add(limit.min(MAX_PAGE)));
+    let mut tokens = Vec::new(e);
+
+    for index in start..end {
+        tokens.push_back(e.storage().persistent().get(&Datakey::ProfileToken(caller.clone(), kind.clone(), index)).unwrap());
     }
 
+    tokens
 }
 
-fn hash_string(env: &Env, s: &String) -> BytesN<32> {
-    let len = s.len() as usize;
-    let mut bytes = [0u8; 100];
-    let bytes = &mut bytes[0..len];
-    s.copy_into_slice(bytes);
-    let mut b = Bytes::new(env);
-    b.copy_from_slice(0, bytes);
-    env.crypto().sha256(&b).to_bytes()
+// The refund policy in force, with a due schedule applied.
+fn read_refund_policy(e: &Env) -> RefundPolicy {
+    match e.storage().persistent().get::<_, RefundSchedule>(&Datakey::RefundSchedule) {
+        Some(schedule) if schedule.effective <= e.ledger().sequence() => schedule.policy,
+        _ => e.storage().persistent().get(&Datakey::RefundPolicy).unwrap_or(RefundPolicy {
+            percent: 0,
+            window: 0,
+        }),
+    }
+}
+
+fn settle_refund_policy(e: &Env) -> RefundPolicy {
+    let policy = read_refund_policy(e);
+
+    if e.storage().persistent().get::<_, RefundSchedule>(&Datakey::RefundSchedule)
+        .is_some_and(|schedule| schedule.effective <= e.ledger().sequence()) {
+        e.storage().persistent().set(&Datakey::RefundPolicy, &policy);
+        e.storage().persistent().remove(&Datakey::RefundSchedule);
+    }
+
+    policy
+}
+
+fn refund_amount(e: &Env, member: &Member, policy: &RefundPolicy) -> i128 {
+    let full = member.paid * policy.percent as i128 / 100;
+
+    if policy.window == 0 {
+        return full;
+    }
+
+    let age = e.ledger().timestamp().saturating_sub(member.joined);
+
+    if age >= policy.window {
+        return 0;
+    }
+
+    full * (policy.window - age) as i128 / policy.window as i128
+}
+
+fn read_referral_policy(e: &Env) -> ReferralPolicy {
+    e.storage().persistent().get(&Datakey::ReferralPolicy).unwrap_or(ReferralPolicy {
+        bonus: 0,
+        cap: 0,
+        period: 0,
+    })
+}
+
+// Records who referred a newcomer and mints the referral bonus, provided the
+// referrer is an active member and still under their cap for the period.
+fn record_referral(e: &Env, referrer: &Address, newcomer: &Address) {
+    if referrer == newcomer {
+        panic!("cannot refer yourself");
+    }
+
+    // suspended or banned members keep their record but must not earn
+    // referral bonuses
+    match read_member(e, referrer) {
+        Some(member) if member_status(e, &member) != MemberStatus::Expired
+            && !is_banned(e, referrer)
+            && !is_suspended(e, referrer) => {}
+        _ => panic!("referrer not a member"),
+    }
+
+    let policy = read_referral_policy(e);
+    let now = e.ledger().timestamp();
+    let key = Datakey::Referrals(referrer.clone());
+    let mut stats: ReferralStats = e.storage().persistent().get(&key).unwrap_or(ReferralStats {
+        count: 0,
+        period_start: now,
+        period_count: 0,
+    });
+
+    if policy.period > 0 && now >= stats.period_start + policy.period {
+        stats.period_start = now;
+        stats.period_count = 0;
+    }
+
+    let mut bonus: i128 = 0;
+    let opus: Option<Address> = e.storage().instance().get(&OPUS);
+
+    if let Some(opus_address) = opus {
+        if policy.bonus > 0 && (policy.cap == 0 || stats.period_count < policy.cap) {
+            bonus = mint_opus(e, &opus_address, referrer, policy.bonus);
+            stats.period_count += 1;
+        }
+    }
+
+    stats.count += 1;
+    e.storage().persistent().set(&key, &stats);
+    e.storage().persistent().set(&Datakey::Referrer(newcomer.clone()), referrer);
+
+    e.events().publish((REFER, symbol_short!("member")), (referrer.clone(), newcomer.clone(), bonus));
+}
+
+// Mints an opus reward, trimmed to what the emission schedule still allows.
+// Returns the amount actually minted.
+fn mint_opus(e: &Env, opus_address: &Address, to: &Address, amount: i128) -> i128 {
+    let opus = opus_token::Client::new(e, opus_address);
+    let amount = amount.min(opus.remaining_emission());
+
+    if amount > 0 {
+        opus.mint(to, &amount);
+    }
+
+    amount
+}
+
+fn read_stored_term(e: &Env) -> MembershipTerm {
+    e.storage().persistent().get(&Datakey::Term).unwrap_or(MembershipTerm {
+        duration: 0,
+        grace: 0,
+        renew_fee: 0,
+    })
+}
+
+// The membership term in force, with a due schedule applied.
+fn read_term(e: &Env) -> MembershipTerm {
+    match e.storage().persistent().get::<_, TermSchedule>(&Datakey::TermSchedule) {
+        Some(schedule) if schedule.effective <= e.ledger().sequence() => schedule.term,
+        _ => read_stored_term(e),
+    }
+}
+
+fn settle_term(e: &Env) -> MembershipTerm {
+    let term = read_term(e);
+
+    if e.storage().persistent().get::<_, TermSchedule>(&Datakey::TermSchedule)
+        .is_some_and(|schedule| schedule.effective <= e.ledger().sequence()) {
+        e.storage().persistent().set(&Datakey::Term, &term);
+        e.storage().persistent().remove(&Datakey::TermSchedule);
+    }
+
+    term
+}
+
+fn member_status(e: &Env, member: &Member) -> MemberStatus {
+    if member.expires == 0 {
+        return MemberStatus::Active;
+    }
+
+    let now = e.ledger().timestamp();
+    let grace = read_term(e).grace;
+
+    if now <= member.expires {
+        MemberStatus::Active
+    } else if now <= member.expires + grace {
+        MemberStatus::Grace
+    } else {
+        MemberStatus::Expired
+    }
+}
+
+// Collectives deployed before amounts were i128 are still stored as
+// `CollectiveV1`; they are upgraded on the next fee write.
+fn read_stored_collective(e: &Env) -> Collective {
+    let val: Val = storage_g(e.clone(), Kind::Permanent, Datakey::Collective).expect("cound not find collective");
+
+    if let Ok(collective) = Collective::try_from_val(e, &val) {
+        return collective;
+    }
+
+    let legacy = CollectiveV1::try_from_val(e, &val).expect("cound not find collective");
+    Collective {
+        symbol: legacy.symbol,
+        join_fee: legacy.join_fee as i128,
+        mint_fee: legacy.mint_fee as i128,
+        pay_token: legacy.pay_token,
+        opus_reward: legacy.opus_reward as i128,
+    }
+}
+
+fn read_fee_schedule(e: &Env) -> Option<FeeSchedule> {
+    e.storage().persistent().get(&Datakey::FeeSchedule)
+}
+
+// The collective with any scheduled fees that are due already applied.
+fn read_collective(e: &Env) -> Collective {
+    let mut collective = read_stored_collective(e);
+
+    if let Some(schedule) = read_fee_schedule(e) {
+        if schedule.effective <= e.ledger().sequence() {
+            collective.join_fee = schedule.join_fee;
+            collective.mint_fee = schedule.mint_fee;
+            collective.opus_reward = schedule.opus_reward;
+        }
+    }
+
+    collective
+}
+
+// Writes a due schedule into the stored collective so new schedules start
+// from the fees actually in force.
+fn settle_fees(e: &Env) -> Collective {
+    let collective = read_collective(e);
+
+    if read_fee_schedule(e).is_some_and(|schedule| schedule.effective <= e.ledger().sequence()) {
+        storage_p(e.clone(), collective.clone(), Kind::Permanent, Datakey::Collective);
+        e.storage().persistent().remove(&Datakey::FeeSchedule);
+    }
+
+    collective
+}
+
+// Fees a single-fee update starts from: the pending schedule if there is
+// one, otherwise the fees in force, due after the usual notice.
+fn next_fees(e: &Env) -> FeeSchedule {
+    let effective = e.ledger().sequence() + FEE_NOTICE;
+
+    match read_fee_schedule(e) {
+        Some(schedule) if schedule.effective > e.ledger().sequence() => FeeSchedule { effective, ..schedule },
+        _ => {
+            let collective = read_collective(e);
+            FeeSchedule {
+                join_fee: collective.join_fee,
+                mint_fee: collective.mint_fee,
+                opus_reward: collective.opus_reward,
+                effective,
+            }
+        }
+    }
+}
+
+fn read_tier(e: &Env, collective: &Collective, name: &Symbol) -> Option<Tier> {
+    if *name == DEFAULT_TIER {
+        return Some(Tier {
+            name: DEFAULT_TIER,
+            join_fee: collective.join_fee,
+            mint_discount: 0,
+            reward_multiplier: 100,
+            actions: ALL_ACTIONS,
+        });
+    }
+
+    let tier: Tier = e.storage().persistent().get(&Datakey::Tier(name.clone()))?;
+
+    match e.storage().persistent().get::<_, TierSchedule>(&Datakey::TierSchedule(name.clone())) {
+        Some(schedule) if schedule.effective <= e.ledger().sequence() => {
+            if schedule.removed { None } else { Some(schedule.tier) }
+        }
+        _ => Some(tier),
+    }
+}
+
+// Writes a due tier schedule into the tier, or deletes a tier whose removal
+// is due, and returns the tier in force, or None if there is none.
+fn settle_tier(e: &Env, name: &Symbol) -> Option<Tier> {
+    let collective = read_collective(e);
+    let tier = read_tier(e, &collective, name);
+    let key = Datakey::TierSchedule(name.clone());
+
+    if e.storage().persistent().get::<_, TierSchedule>(&key).is_some_and(|schedule| schedule.effective <= e.ledger().sequence()) {
+        match &tier {
+            Some(tier) => e.storage().persistent().set(&Datakey::Tier(name.clone()), tier),
+            None => {
+                let mut tiers: Vec<Symbol> = e.storage().persistent().get(&Datakey::Tiers).unwrap_or(Vec::new(e));
+                if let Some(index) = tiers.first_index_of(name) {
+                    tiers.remove(index);
+                    e.storage().persistent().set(&Datakey::Tiers, &tiers);
+                }
+                e.storage().persistent().remove(&Datakey::Tier(name.clone()));
+            }
+        }
+        e.storage().persistent().remove(&key);
+    }
+
+    tier
+}
+
+// Tier that applies to the caller, falling back to the default tier for the
+// admin, non-members and members whose tier has since been removed.
+fn caller_tier(e: &Env, caller: &Address) -> Tier {
+    let collective = read_collective(e);
+    let default = read_tier(e, &collective, &DEFAULT_TIER).unwrap();
+
+    match read_member(e, caller) {
+        Some(member) => read_tier(e, &collective, &member.tier).unwrap_or(default),
+        None => default,
+    }
+}
+
+fn require_action(tier: &Tier, action: u32) {
+    if tier.actions & action == 0 {
+        panic!("tier not permitted");
+    }
+}
+
+fn discounted_fee(fee: i128, discount: u32) -> i128 {
+    fee.checked_mul(100 - discount as i128).expect("amount overflow") / 100
+}
+
+fn verify_voucher(e: &Env, caller: &Address, voucher: &Voucher, signer: &BytesN<32>, signature: &BytesN<64>) {
+    if voucher.collective != e.current_contract_address() || voucher.recipient != *caller {
+        panic!("voucher not valid here");
+    }
+
+    if voucher.fee != JOIN_FEE && voucher.fee != MINT_FEE {
+        panic!("unknown voucher fee");
+    }
+
+    if voucher.discount > 100 {
+        panic!("discount must not exceed 100");
+    }
+
+    if e.ledger().timestamp() > voucher.expires {
+        panic!("voucher expired");
+    }
+
+    if !e.storage().persistent().has(&Datakey::VoucherKey(signer.clone())) {
+        panic!("unknown voucher key");
+    }
+
+    let nonce = Datakey::VoucherNonce(signer.clone(), voucher.nonce);
+
+    if e.storage().persistent().has(&nonce) {
+        panic!("voucher already used");
+    }
+
+    e.crypto().ed25519_verify(signer, &voucher.clone().to_xdr(e), signature);
+    e.storage().persistent().set(&nonce, &true);
+}
+
+// Mint fee after the tier discount and any voucher credit, which is used up.
+fn member_mint_fee(e: &Env, caller: &Address, collective: &Collective, tier: &Tier) -> i128 {
+    let fee = discounted_fee(collective.mint_fee, tier.mint_discount);
+    let credit: Option<u32> = e.storage().persistent().get(&Datakey::MintCredit(caller.clone()));
+
+    match credit {
+        Some(discount) => {
+            e.storage().persistent().remove(&Datakey::MintCredit(caller.clone()));
+            fee * (100 - discount as i128) / 100
+        }
+        None => fee,
+    }
 }
 
 fn validate_negative_amount(amount: i128)-> bool {
-    let amt: u32 = amount as u32;
-    amt ==0 || (amt & 0xffffffff) > 0
+    amount >= 0
+}
+
+fn require_valid_amount(amount: i128) {
+    if !validate_negative_amount(amount) {
+        panic!("invalid amount, must be non-negative");
+    }
+
+    if amount > MAX_AMOUNT {
+        panic!("amount exceeds maximum");
+    }
 }
 
 fn storage_g<T: IntoVal<Env, Val> + TryFromVal<Env, Val>>(
@@ -450,6 +3056,71 @@ fn storage_p<T: IntoVal<Env, Val>>(env: Env, value: T, kind: Kind, key: Datakey)
     done
 }
 
+fn read_council(e: &Env) -> Council {
+    e.storage().persistent().get(&Datakey::Council).expect("no council")
+}
+
+fn store_council(e: &Env, signers: Vec<Address>, threshold: u32, window: u32) {
+    if threshold == 0 || threshold > signers.len() {
+        panic!("invalid threshold");
+    }
+
+    if window == 0 {
+        panic!("invalid window");
+    }
+
+    for (i, signer) in signers.iter().enumerate() {
+        if signers.first_index_of(&signer) != Some(i as u32) {
+            panic!("duplicate signer");
+        }
+    }
+
+    let council = Council { signers, threshold, window };
+    e.storage().persistent().set(&Datakey::Council, &council);
+    e.events().publish((COUNCIL, symbol_short!("set")), (council.signers.len(), threshold));
+}
+
+fn require_no_council(e: &Env) {
+    if e.storage().persistent().has(&Datakey::Council) {
+        panic!("council approval required");
+    }
+}
+
+fn read_open_proposal(e: &Env, id: u32) -> Proposal {
+    let proposal: Proposal = e.storage().persistent().get(&Datakey::Proposal(id)).expect("no proposal");
+
+    if proposal.executed {
+        panic!("proposal already executed");
+    }
+
+    if e.ledger().sequence() > proposal.expires {
+        panic!("proposal expired");
+    }
+
+    proposal
+}
+
+// Approvals from signers that have since left the council no longer count.
+fn approval_count(council: &Council, proposal: &Proposal) -> u32 {
+    proposal.approvals.iter().filter(|signer| council.signers.contains(signer)).count() as u32
+}
+
+fn read_grant(e: &Env, id: u32) -> Grant {
+    e.storage().persistent().get(&Datakey::Grant(id)).expect("no grant")
+}
+
+fn vested_amount(e: &Env, grant: &Grant) -> i128 {
+    let now = e.ledger().timestamp();
+
+    if grant.revoked || now >= grant.end {
+        grant.amount
+    } else if now < grant.cliff {
+        0
+    } else {
+        grant.amount * (now - grant.start) as i128 / (grant.end - grant.start) as i128
+    }
+}
+
 
 
 mod test;
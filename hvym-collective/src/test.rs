@@ -1,11 +1,13 @@
 #![cfg(test)]
 
-use crate::{CollectiveContract, CollectiveContractClient};
+use crate::{CollectiveContract, CollectiveContractClient, CollectiveV1, CouncilAction, Datakey, DIVIDEND_WINDOW, FEE_NOTICE, INCOME_JOIN, JoinMode, MembershipTerm, MemberStatus, ReferralPolicy, RefundPolicy, Split, Tier, Voucher, ALL_ACTIONS, ACTION_PUBLISH, MODERATOR, TREASURER};
 use crate::{token};
 use soroban_sdk::{
-    testutils::{Address as _, Events}, testutils::arbitrary::std,
-    Symbol, Address, Env, String, FromVal, TryFromVal, symbol_short
+    testutils::{Address as _, Events, Ledger}, testutils::arbitrary::std,
+    Symbol, Address, Env, String, FromVal, TryFromVal, BytesN, symbol_short, vec,
+    xdr::ToXdr
 };
+use ed25519_dalek::{Signer, SigningKey};
 
 mod pintheon_node_token {
     soroban_sdk::contractimport!(
@@ -25,6 +27,12 @@ mod opus_token {
     );
 }
 
+mod opus_staking {
+    soroban_sdk::contractimport!(
+        file = "../opus_staking/target/wasm32-unknown-unknown/release/opus_staking.optimized.wasm"
+    );
+}
+
 fn create_token_contract<'a>(
     e: &Env,
     admin: &Address,
@@ -36,6 +44,15 @@ fn create_token_contract<'a>(
     )
 }
 
+// Keeps storage alive while a test waits out the fee notice period.
+fn keep_entries_alive(env: &Env) {
+    env.ledger().with_mut(|li| {
+        li.min_persistent_entry_ttl = 2 * DIVIDEND_WINDOW;
+        li.min_temp_entry_ttl = 2 * DIVIDEND_WINDOW;
+        li.max_entry_ttl = 4 * DIVIDEND_WINDOW;
+    });
+}
+
 #[test]
 fn test_join_and_remove() {
     let env = Env::default();
@@ -47,14 +64,14 @@ fn test_join_and_remove() {
 
     let collective = CollectiveContractClient::new(
         &env,
-        &env.register(CollectiveContract, (&admin, 10_u32, 5_u32, &pay_token_client.address, 3_u32))
+        &env.register(CollectiveContract, (&admin, 10_i128, 5_i128, &pay_token_client.address, 3_i128))
     );
 
     assert_eq!(collective.is_member(&user), false);
     collective.join(&user);
     assert_eq!(collective.is_member(&user), true);
     assert_eq!(collective.member_paid(&user), 10);
-    assert_eq!(collective.remove(&user), true);
+    assert_eq!(collective.remove(&admin, &user), true);
     assert_eq!(collective.is_member(&user), false);
 }
 
@@ -70,7 +87,7 @@ fn test_double_join_should_fail() {
 
     let collective = CollectiveContractClient::new(
         &env,
-        &env.register(CollectiveContract, (&admin, 10_u32, 5_u32, &pay_token_client.address, 3_u32))
+        &env.register(CollectiveContract, (&admin, 10_i128, 5_i128, &pay_token_client.address, 3_i128))
     );
 
     collective.join(&user);
@@ -89,7 +106,7 @@ fn test_join_with_insufficient_balance() {
 
     let collective = CollectiveContractClient::new(
         &env,
-        &env.register(CollectiveContract, (&admin, 10_u32, 5_u32, &pay_token_client.address, 3_u32))
+        &env.register(CollectiveContract, (&admin, 10_i128, 5_i128, &pay_token_client.address, 3_i128))
     );
 
     collective.join(&user); // should panic
@@ -99,17 +116,151 @@ fn test_join_with_insufficient_balance() {
 fn test_update_fees_and_reward() {
     let env = Env::default();
     env.mock_all_auths();
+    keep_entries_alive(&env);
     let admin = Address::generate(&env);
     let (pay_token_client, _) = create_token_contract(&env, &admin);
 
     let collective = CollectiveContractClient::new(
         &env,
-        &env.register(CollectiveContract, (&admin, 10_u32, 5_u32, &pay_token_client.address, 3_u32))
+        &env.register(CollectiveContract, (&admin, 10_i128, 5_i128, &pay_token_client.address, 3_i128))
+    );
+
+    assert_eq!(collective.update_join_fee(&admin, &20), 20);
+    assert_eq!(collective.update_mint_fee(&admin, &15), 15);
+    assert_eq!(collective.update_opus_reward(&admin, &8), 8);
+
+    // updates are queued together and only apply after the notice period
+    let pending = collective.pending_fees().unwrap();
+    assert_eq!((pending.join_fee, pending.mint_fee, pending.opus_reward), (20, 15, 8));
+    assert_eq!(pending.effective, env.ledger().sequence() + FEE_NOTICE);
+    assert_eq!(collective.join_fee(), 10);
+
+    env.ledger().with_mut(|li| li.sequence_number += FEE_NOTICE);
+    assert_eq!(collective.join_fee(), 20);
+    assert_eq!(collective.mint_fee(), 15);
+    assert_eq!(collective.opus_reward(), 8);
+}
+
+#[test]
+fn test_scheduled_fees() {
+    let env = Env::default();
+    env.mock_all_auths();
+    keep_entries_alive(&env);
+    let admin = Address::generate(&env);
+    let user = Address::generate(&env);
+    let (pay_token_client, pay_token_admin_client) = create_token_contract(&env, &admin);
+    pay_token_admin_client.mint(&user, &100);
+
+    let collective = CollectiveContractClient::new(
+        &env,
+        &env.register(CollectiveContract, (&admin, 10_i128, 5_i128, &pay_token_client.address, 3_i128))
+    );
+
+    let effective = env.ledger().sequence() + FEE_NOTICE;
+    assert!(collective.try_schedule_fees(&admin, &40, &7, &4, &(effective - 1)).is_err());
+    collective.schedule_fees(&admin, &40, &7, &4, &effective);
+    assert_eq!(collective.pending_fees().unwrap().join_fee, 40);
+    assert_eq!(collective.join_fee(), 10);
+
+    env.ledger().with_mut(|li| li.sequence_number = effective);
+    assert_eq!(collective.pending_fees(), None);
+    assert_eq!(collective.join_fee(), 40);
+    assert_eq!(collective.mint_fee(), 7);
+    assert_eq!(collective.opus_reward(), 4);
+
+    collective.join(&user);
+    assert_eq!(collective.member_paid(&user), 40);
+
+    assert_eq!(collective.update_mint_fee(&admin, &9), 9);
+    assert_eq!(collective.mint_fee(), 7);
+
+    env.ledger().with_mut(|li| li.sequence_number += FEE_NOTICE);
+    assert_eq!(collective.join_fee(), 40);
+    assert_eq!(collective.mint_fee(), 9);
+}
+
+#[test]
+fn test_tier_and_term_changes_need_notice() {
+    let env = Env::default();
+    env.mock_all_auths();
+    keep_entries_alive(&env);
+    let admin = Address::generate(&env);
+    let user = Address::generate(&env);
+    let (pay_token_client, pay_token_admin_client) = create_token_contract(&env, &admin);
+    pay_token_admin_client.mint(&user, &100);
+
+    let collective = CollectiveContractClient::new(
+        &env,
+        &env.register(CollectiveContract, (&admin, 10_i128, 5_i128, &pay_token_client.address, 3_i128))
+    );
+
+    // new tiers and the first term apply straight away
+    let patron = symbol_short!("patron");
+    collective.set_tier(&admin, &patron, &20, &0_u32, &100_u32, &ALL_ACTIONS);
+    collective.set_membership_term(&admin, &1000, &100, &4);
+    assert_eq!(collective.tier(&patron).join_fee, 20);
+    assert_eq!(collective.membership_term().renew_fee, 4);
+
+    collective.set_tier(&admin, &patron, &30, &0_u32, &100_u32, &ALL_ACTIONS);
+    collective.set_membership_term(&admin, &1000, &100, &6);
+    assert_eq!(collective.pending_tier(&patron).unwrap().tier.join_fee, 30);
+    assert_eq!(collective.pending_term().unwrap().term.renew_fee, 6);
+
+    collective.join_tier(&user, &patron, &None);
+    assert_eq!(collective.member_paid(&user), 20);
+    assert_eq!(collective.membership_term().renew_fee, 4);
+
+    env.ledger().with_mut(|li| li.sequence_number += FEE_NOTICE);
+    assert_eq!(collective.pending_tier(&patron), None);
+    assert_eq!(collective.pending_term(), None);
+    assert_eq!(collective.tier(&patron).join_fee, 30);
+    assert_eq!(collective.membership_term().renew_fee, 6);
+
+    // a later change starts from the terms now in force
+    collective.set_tier(&admin, &patron, &40, &0_u32, &100_u32, &ALL_ACTIONS);
+    assert_eq!(collective.tier(&patron).join_fee, 30);
+}
+
+#[test]
+fn test_tier_removal_and_refund_changes_need_notice() {
+    let env = Env::default();
+    env.mock_all_auths();
+    keep_entries_alive(&env);
+    let admin = Address::generate(&env);
+    let user = Address::generate(&env);
+    let (pay_token_client, pay_token_admin_client) = create_token_contract(&env, &admin);
+    pay_token_admin_client.mint(&user, &100);
+
+    let collective = CollectiveContractClient::new(
+        &env,
+        &env.register(CollectiveContract, (&admin, 10_i128, 5_i128, &pay_token_client.address, 3_i128))
     );
 
-    assert_eq!(collective.update_join_fee(&20_u32), 20);
-    assert_eq!(collective.update_mint_fee(&15_u32), 15);
-    assert_eq!(collective.update_opus_reward(&8_u32), 8);
+    let patron = symbol_short!("patron");
+    collective.set_tier(&admin, &patron, &20, &0_u32, &100_u32, &ALL_ACTIONS);
+    collective.set_refund_policy(&admin, &50, &0);
+    collective.join_tier(&user, &patron, &None);
+    assert_eq!(collective.leave_refund(&user), 10);
+
+    assert!(!collective.remove_tier(&admin, &symbol_short!("unknown")));
+    assert!(collective.remove_tier(&admin, &patron));
+    collective.set_refund_policy(&admin, &0, &0);
+
+    // nothing changes for members until the notice runs out
+    assert!(collective.pending_tier(&patron).unwrap().removed);
+    assert_eq!(collective.pending_refund_policy().unwrap().policy.percent, 0);
+    assert_eq!(collective.tiers(), vec![&env, symbol_short!("member"), patron.clone()]);
+    assert_eq!(collective.leave_refund(&user), 10);
+
+    env.ledger().with_mut(|li| li.sequence_number += FEE_NOTICE);
+    assert_eq!(collective.tiers(), vec![&env, symbol_short!("member")]);
+    assert!(collective.try_tier(&patron).is_err());
+    assert_eq!(collective.refund_policy().percent, 0);
+    assert_eq!(collective.leave_refund(&user), 0);
+
+    // a removed tier can be set up again from scratch
+    collective.set_tier(&admin, &patron, &25, &0_u32, &100_u32, &ALL_ACTIONS);
+    assert_eq!(collective.tier(&patron).join_fee, 25);
 }
 
 #[test]
@@ -123,12 +274,12 @@ fn test_fund_and_withdraw() {
 
     let collective = CollectiveContractClient::new(
         &env,
-        &env.register(CollectiveContract, (&admin, 10_u32, 5_u32, &pay_token_client.address, 3_u32))
+        &env.register(CollectiveContract, (&admin, 10_i128, 5_i128, &pay_token_client.address, 3_i128))
     );
 
     collective.fund_contract(&user, &50);
     assert_eq!(pay_token_client.balance(&collective.address), 50);
-    collective.withdraw(&admin);
+    collective.withdraw(&admin, &admin);
     assert_eq!(pay_token_client.balance(&admin), 50);
 }
 
@@ -141,12 +292,13 @@ fn test_deploy_opus() {
 
     let collective = CollectiveContractClient::new(
         &env,
-        &env.register(CollectiveContract, (&admin, 10_u32, 5_u32, &pay_token_client.address, 10_u32))
+        &env.register(CollectiveContract, (&admin, 10_i128, 5_i128, &pay_token_client.address, 10_i128))
     );
 
-    let opus_address = collective.launch_opus(&100);
+    let opus_address = collective.launch_opus(&admin, &100, &1_000_000, &1_000, &10_000);
     let opus_client = opus_token::Client::new(&env, &opus_address);
-    assert_eq!(opus_client.balance(&admin), 100);
+    assert_eq!(opus_client.balance(&admin), 0);
+    assert_eq!(opus_client.balance(&collective.address), 100);
 }
 
 #[test]
@@ -159,11 +311,41 @@ fn test_deploy_opus_twice_should_fail() {
 
     let collective = CollectiveContractClient::new(
         &env,
-        &env.register(CollectiveContract, (&admin, 10_u32, 5_u32, &pay_token_client.address, 10_u32))
+        &env.register(CollectiveContract, (&admin, 10_i128, 5_i128, &pay_token_client.address, 10_i128))
+    );
+
+    collective.launch_opus(&admin, &100, &1_000_000, &1_000, &10_000);
+    collective.launch_opus(&admin, &100, &1_000_000, &1_000, &10_000); // should panic
+}
+
+#[test]
+fn test_member_stake() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let (pay_token_client, _) = create_token_contract(&env, &admin);
+
+    let collective = CollectiveContractClient::new(
+        &env,
+        &env.register(CollectiveContract, (&admin, 10_i128, 5_i128, &pay_token_client.address, 10_i128))
+    );
+    let opus_address = collective.launch_opus(&admin, &100, &1_000_000, &1_000, &10_000);
+    collective.grant_vesting(&admin, &admin, &100, &0, &1, &false);
+    env.ledger().with_mut(|li| li.timestamp += 1);
+    collective.release(&admin, &0);
+    assert_eq!(collective.member_stake(&admin), 0);
+
+    let staking = opus_staking::Client::new(
+        &env,
+        &env.register(opus_staking::WASM, (&admin, &opus_address, 1_000_u32, 2_000_u32))
     );
+    staking.set_lock_options(&vec![&env, opus_staking::LockOption { duration: 2_592_000, multiplier: 100 }]);
+    staking.fund(&admin, &20);
+    staking.stake(&admin, &80, &2_592_000);
 
-    collective.launch_opus(&100);
-    collective.launch_opus(&100); // should panic
+    collective.set_staking(&staking.address);
+    assert_eq!(collective.staking(), Some(staking.address.clone()));
+    assert_eq!(collective.member_stake(&admin), 80);
 }
 
 #[test]
@@ -177,7 +359,7 @@ fn test_deploy_node_token() {
 
     let collective = CollectiveContractClient::new(
         &env,
-        &env.register(CollectiveContract, (&admin, 10_u32, 5_u32, &pay_token_client.address, 5_u32))
+        &env.register(CollectiveContract, (&admin, 10_i128, 5_i128, &pay_token_client.address, 5_i128))
     );
 
     collective.join(&user);
@@ -200,11 +382,11 @@ fn test_deploy_ipfs_token() {
 
     let collective = CollectiveContractClient::new(
         &env,
-        &env.register(CollectiveContract, (&admin, 10_u32, 5_u32, &pay_token_client.address, 5_u32))
+        &env.register(CollectiveContract, (&admin, 10_i128, 5_i128, &pay_token_client.address, 5_i128))
     );
 
     collective.join(&user);
-    collective.launch_opus(&100);
+    collective.launch_opus(&admin, &100, &1_000_000, &1_000, &10_000);
 
     let name = String::from_val(&env, &"MyFile");
     let ipfs_hash = String::from_val(&env, &"QmHash");
@@ -230,7 +412,7 @@ fn test_emits_join_and_remove_events() {
 
     let contract_id = env.register(
         CollectiveContract,
-        (&admin, 10_u32, 5_u32, &pay_token_client.address, 3_u32),
+        (&admin, 10_i128, 5_i128, &pay_token_client.address, 3_i128),
     );
     let collective = CollectiveContractClient::new(&env, &contract_id);
 
@@ -244,7 +426,7 @@ fn test_emits_join_and_remove_events() {
 
     assert_eq!(join_symbol, symbol_short!("JOIN"));
 
-    collective.remove(&user);
+    collective.remove(&admin, &user);
     let events_2: std::vec::Vec<_> = env.events().all().into_iter().collect();
     let remove_event = &events_2[0];
 
@@ -270,7 +452,7 @@ fn test_emits_publish_events() {
 
     let contract_id = env.register(
         CollectiveContract,
-        (&admin, 10_u32, 5_u32, &pay_token_client.address, 3_u32),
+        (&admin, 10_i128, 5_i128, &pay_token_client.address, 3_i128),
     );
     let collective = CollectiveContractClient::new(&env, &contract_id);
 
@@ -311,7 +493,7 @@ fn test_zero_value_funding() {
 
     let collective = CollectiveContractClient::new(
         &env,
-        &env.register(CollectiveContract, (&admin, 10_u32, 5_u32, &pay_token_client.address, 3_u32))
+        &env.register(CollectiveContract, (&admin, 10_i128, 5_i128, &pay_token_client.address, 3_i128))
     );
 
     let result = collective.fund_contract(&user, &0);
@@ -329,7 +511,7 @@ fn test_deploy_contract_as_non_member() {
 
     let collective = CollectiveContractClient::new(
         &env,
-        &env.register(CollectiveContract, (&admin, 10_u32, 5_u32, &pay_token_client.address, 3_u32))
+        &env.register(CollectiveContract, (&admin, 10_i128, 5_i128, &pay_token_client.address, 3_i128))
     );
 
     let name = String::from_val(&env, &"ForbiddenNode");
@@ -349,7 +531,7 @@ fn test_deploy_ipfs_before_opus() {
 
     let collective = CollectiveContractClient::new(
         &env,
-        &env.register(CollectiveContract, (&admin, 10_u32, 5_u32, &pay_token_client.address, 3_u32))
+        &env.register(CollectiveContract, (&admin, 10_i128, 5_i128, &pay_token_client.address, 3_i128))
     );
 
     collective.join(&user);
@@ -362,3 +544,1570 @@ fn test_deploy_ipfs_before_opus() {
 
     collective.deploy_ipfs_token(&user, &name, &ipfs_hash, &file_type, &gateways, &ipns_hash);
 }
+
+#[test]
+fn test_join_tier_and_upgrade() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let user = Address::generate(&env);
+    let (pay_token_client, pay_token_admin_client) = create_token_contract(&env, &admin);
+    pay_token_admin_client.mint(&user, &100);
+
+    let collective = CollectiveContractClient::new(
+        &env,
+        &env.register(CollectiveContract, (&admin, 10_i128, 5_i128, &pay_token_client.address, 3_i128))
+    );
+
+    let creator = symbol_short!("creator");
+    collective.set_tier(&admin, &creator, &25, &50_u32, &200_u32, &ALL_ACTIONS);
+    assert_eq!(collective.tiers().len(), 2);
+
+    collective.join(&user);
+    assert_eq!(collective.member_tier(&user), symbol_short!("member"));
+
+    collective.upgrade_tier(&user, &creator);
+    assert_eq!(collective.member_tier(&user), creator);
+    assert_eq!(collective.member_paid(&user), 25);
+    assert_eq!(pay_token_client.balance(&user), 75);
+}
+
+#[test]
+fn test_tier_discount_and_reward() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let user = Address::generate(&env);
+    let (pay_token_client, pay_token_admin_client) = create_token_contract(&env, &admin);
+    pay_token_admin_client.mint(&user, &100);
+
+    let collective = CollectiveContractClient::new(
+        &env,
+        &env.register(CollectiveContract, (&admin, 10_i128, 10_i128, &pay_token_client.address, 5_i128))
+    );
+
+    let creator = symbol_short!("creator");
+    collective.set_tier(&admin, &creator, &20, &50_u32, &200_u32, &ALL_ACTIONS);
+    collective.join_tier(&user, &creator, &None);
+    let opus_address = collective.launch_opus(&admin, &100, &1_000_000, &1_000, &10_000);
+
+    let name = String::from_val(&env, &"MyFile");
+    let ipfs_hash = String::from_val(&env, &"QmHash");
+    let file_type = String::from_val(&env, &"image/png");
+    let gateways = String::from_val(&env, &"https://ipfs.io");
+    collective.deploy_ipfs_token(&user, &name, &ipfs_hash, &file_type, &gateways, &None);
+
+    let opus_client = opus_token::Client::new(&env, &opus_address);
+    assert_eq!(pay_token_client.balance(&user), 75);
+    assert_eq!(opus_client.balance(&user), 10);
+}
+
+#[test]
+#[should_panic(expected = "tier not permitted")]
+fn test_tier_without_node_action() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let user = Address::generate(&env);
+    let (pay_token_client, pay_token_admin_client) = create_token_contract(&env, &admin);
+    pay_token_admin_client.mint(&user, &100);
+
+    let collective = CollectiveContractClient::new(
+        &env,
+        &env.register(CollectiveContract, (&admin, 10_i128, 5_i128, &pay_token_client.address, 3_i128))
+    );
+
+    let supporter = symbol_short!("supporter");
+    collective.set_tier(&admin, &supporter, &5, &0_u32, &100_u32, &ACTION_PUBLISH);
+    collective.join_tier(&user, &supporter, &None);
+
+    let name = String::from_val(&env, &"MyNode");
+    let descriptor = String::from_val(&env, &"This is a node");
+    collective.deploy_node_token(&user, &name, &descriptor);
+}
+
+#[test]
+fn test_membership_expiry_and_renewal() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let user = Address::generate(&env);
+    let (pay_token_client, pay_token_admin_client) = create_token_contract(&env, &admin);
+    pay_token_admin_client.mint(&user, &100);
+
+    let collective = CollectiveContractClient::new(
+        &env,
+        &env.register(CollectiveContract, (&admin, 10_i128, 5_i128, &pay_token_client.address, 3_i128))
+    );
+
+    collective.set_membership_term(&admin, &100, &50, &4);
+    collective.join(&user);
+    assert_eq!(collective.member_expires(&user), 100);
+    assert_eq!(collective.member_status(&user), MemberStatus::Active);
+
+    env.ledger().with_mut(|li| li.timestamp = 120);
+    assert_eq!(collective.member_status(&user), MemberStatus::Grace);
+    assert!(collective.is_member(&user));
+
+    env.ledger().with_mut(|li| li.timestamp = 200);
+    assert_eq!(collective.member_status(&user), MemberStatus::Expired);
+    assert!(!collective.is_member(&user));
+
+    assert_eq!(collective.renew(&user), 300);
+    assert_eq!(collective.member_status(&user), MemberStatus::Active);
+    assert_eq!(pay_token_client.balance(&user), 86);
+}
+
+#[test]
+fn test_renew_within_grace_keeps_term() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let user = Address::generate(&env);
+    let (pay_token_client, pay_token_admin_client) = create_token_contract(&env, &admin);
+    pay_token_admin_client.mint(&user, &100);
+
+    let collective = CollectiveContractClient::new(
+        &env,
+        &env.register(CollectiveContract, (&admin, 10_i128, 5_i128, &pay_token_client.address, 3_i128))
+    );
+
+    collective.set_membership_term(&admin, &100, &50, &4);
+    collective.join(&user);
+
+    env.ledger().with_mut(|li| li.timestamp = 130);
+    assert_eq!(collective.renew(&user), 200);
+}
+
+#[test]
+fn test_leave_with_decaying_refund() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let user = Address::generate(&env);
+    let (pay_token_client, pay_token_admin_client) = create_token_contract(&env, &admin);
+    pay_token_admin_client.mint(&user, &100);
+
+    let collective = CollectiveContractClient::new(
+        &env,
+        &env.register(CollectiveContract, (&admin, 20_i128, 5_i128, &pay_token_client.address, 3_i128))
+    );
+
+    collective.set_refund_policy(&admin, &50_u32, &100);
+    collective.join(&user);
+    assert_eq!(collective.leave_refund(&user), 10);
+
+    env.ledger().with_mut(|li| li.timestamp = 50);
+    assert_eq!(collective.leave(&user), 5);
+
+    let events: std::vec::Vec<_> = env.events().all().into_iter().collect();
+    let (_contract_id, topics, _data) = events.last().unwrap();
+    let leave_symbol: Symbol = Symbol::try_from_val(&env, &topics.get_unchecked(0))
+        .expect("Expected first topic to be a Symbol");
+
+    assert_eq!(leave_symbol, symbol_short!("LEAVE"));
+    assert!(!collective.is_member(&user));
+    assert_eq!(pay_token_client.balance(&user), 85);
+}
+
+#[test]
+#[should_panic(expected = "not a member")]
+fn test_leave_as_non_member() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let user = Address::generate(&env);
+    let (pay_token_client, _) = create_token_contract(&env, &admin);
+
+    let collective = CollectiveContractClient::new(
+        &env,
+        &env.register(CollectiveContract, (&admin, 10_i128, 5_i128, &pay_token_client.address, 3_i128))
+    );
+
+    collective.leave(&user);
+}
+
+#[test]
+fn test_referral_bonus_and_cap() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let referrer = Address::generate(&env);
+    let (pay_token_client, pay_token_admin_client) = create_token_contract(&env, &admin);
+    pay_token_admin_client.mint(&referrer, &100);
+
+    let collective = CollectiveContractClient::new(
+        &env,
+        &env.register(CollectiveContract, (&admin, 10_i128, 5_i128, &pay_token_client.address, 3_i128))
+    );
+
+    let opus_address = collective.launch_opus(&admin, &100, &1_000_000, &1_000, &10_000);
+    let opus_client = opus_token::Client::new(&env, &opus_address);
+    collective.set_referral_policy(&admin, &7, &2_u32, &1000);
+    collective.join(&referrer);
+
+    for _ in 0..3 {
+        let newcomer = Address::generate(&env);
+        pay_token_admin_client.mint(&newcomer, &100);
+        collective.join_tier(&newcomer, &symbol_short!("member"), &Some(referrer.clone()));
+        assert_eq!(collective.referrer_of(&newcomer), Some(referrer.clone()));
+    }
+
+    assert_eq!(collective.referral_count(&referrer), 3);
+    assert_eq!(opus_client.balance(&referrer), 14);
+
+    env.ledger().with_mut(|li| li.timestamp = 1000);
+    let newcomer = Address::generate(&env);
+    pay_token_admin_client.mint(&newcomer, &100);
+    collective.join_tier(&newcomer, &symbol_short!("member"), &Some(referrer.clone()));
+    assert_eq!(opus_client.balance(&referrer), 21);
+}
+
+#[test]
+#[should_panic(expected = "referrer not a member")]
+fn test_referral_from_non_member() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let user = Address::generate(&env);
+    let stranger = Address::generate(&env);
+    let (pay_token_client, pay_token_admin_client) = create_token_contract(&env, &admin);
+    pay_token_admin_client.mint(&user, &100);
+
+    let collective = CollectiveContractClient::new(
+        &env,
+        &env.register(CollectiveContract, (&admin, 10_i128, 5_i128, &pay_token_client.address, 3_i128))
+    );
+
+    collective.join_tier(&user, &symbol_short!("member"), &Some(stranger));
+}
+
+#[test]
+#[should_panic(expected = "referrer not a member")]
+fn test_referral_from_suspended_member() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let referrer = Address::generate(&env);
+    let user = Address::generate(&env);
+    let (pay_token_client, pay_token_admin_client) = create_token_contract(&env, &admin);
+    pay_token_admin_client.mint(&referrer, &100);
+    pay_token_admin_client.mint(&user, &100);
+
+    let collective = CollectiveContractClient::new(
+        &env,
+        &env.register(CollectiveContract, (&admin, 10_i128, 5_i128, &pay_token_client.address, 3_i128))
+    );
+
+    collective.launch_opus(&admin, &100, &1_000_000, &1_000, &10_000);
+    collective.set_referral_policy(&admin, &7, &0_u32, &0);
+    collective.join(&referrer);
+    collective.suspend(&admin, &referrer, &100, &String::from_val(&env, &"spam"));
+
+    collective.join_tier(&user, &symbol_short!("member"), &Some(referrer));
+}
+
+#[test]
+fn test_index_legacy_members() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let legacy = Address::generate(&env);
+    let user = Address::generate(&env);
+    let stranger = Address::generate(&env);
+    let (pay_token_client, pay_token_admin_client) = create_token_contract(&env, &admin);
+    pay_token_admin_client.mint(&user, &100);
+
+    let collective = CollectiveContractClient::new(
+        &env,
+        &env.register(CollectiveContract, (&admin, 10_i128, 5_i128, &pay_token_client.address, 3_i128))
+    );
+
+    // members of the original contract were stored as the fee they paid
+    env.as_contract(&collective.address, || {
+        env.storage().persistent().set(&Datakey::Member(legacy.clone()), &10_u32);
+    });
+    collective.join(&user);
+    assert_eq!(collective.member_count(), 1);
+
+    assert_eq!(collective.index_members(&vec![&env, legacy.clone(), user.clone(), stranger]), 1);
+    assert_eq!(collective.member_count(), 2);
+    assert_eq!(collective.members(&0, &10).get(1).unwrap().address, legacy);
+    assert_eq!(collective.member(&legacy).paid, 10);
+
+    // indexing again is a no-op
+    assert_eq!(collective.index_members(&vec![&env, legacy.clone()]), 0);
+    assert_eq!(collective.member_count(), 2);
+}
+
+#[test]
+fn test_member_registry() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let (pay_token_client, pay_token_admin_client) = create_token_contract(&env, &admin);
+
+    let collective = CollectiveContractClient::new(
+        &env,
+        &env.register(CollectiveContract, (&admin, 10_i128, 5_i128, &pay_token_client.address, 3_i128))
+    );
+
+    let users: std::vec::Vec<Address> = (0..3).map(|_| Address::generate(&env)).collect();
+    for (i, user) in users.iter().enumerate() {
+        env.ledger().with_mut(|li| {
+            li.sequence_number = 10 + i as u32;
+            li.timestamp = 100 * i as u64;
+        });
+        pay_token_admin_client.mint(user, &100);
+        collective.join(user);
+    }
+
+    assert_eq!(collective.member_count(), 3);
+    assert_eq!(collective.member(&users[1]).joined_ledger, 11);
+    assert_eq!(collective.member(&users[1]).joined, 100);
+
+    let page = collective.members(&1, &5);
+    assert_eq!(page.len(), 2);
+    assert_eq!(page.get_unchecked(0).address, users[1]);
+
+    collective.remove(&admin, &users[0]);
+    collective.leave(&users[2]);
+    assert_eq!(collective.member_count(), 1);
+
+    let page = collective.members(&0, &5);
+    assert_eq!(page.len(), 1);
+    assert_eq!(page.get_unchecked(0).address, users[1]);
+}
+
+#[test]
+fn test_member_profile() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let user = Address::generate(&env);
+    let (pay_token_client, pay_token_admin_client) = create_token_contract(&env, &admin);
+    pay_token_admin_client.mint(&user, &100);
+
+    let collective = CollectiveContractClient::new(
+        &env,
+        &env.register(CollectiveContract, (&admin, 10_i128, 5_i128, &pay_token_client.address, 3_i128))
+    );
+
+    collective.join(&user);
+    collective.launch_opus(&admin, &100, &1_000_000, &1_000, &10_000);
+
+    let handle = String::from_val(&env, &"artist");
+    let avatar = String::from_val(&env, &"QmAvatar");
+    let bio = String::from_val(&env, &"QmBio");
+    collective.update_profile(&user, &handle, &avatar, &bio);
+
+    let node = collective.deploy_node_token(&user, &String::from_val(&env, &"MyNode"), &String::from_val(&env, &"This is a node"));
+    let file = collective.deploy_ipfs_token(
+        &user,
+        &String::from_val(&env, &"MyFile"),
+        &String::from_val(&env, &"QmHash"),
+        &String::from_val(&env, &"image/png"),
+        &String::from_val(&env, &"https://ipfs.io"),
+        &None,
+    );
+
+    let profile = collective.profile(&user);
+    assert_eq!(profile.handle, handle);
+    assert_eq!(profile.bio, bio);
+    assert_eq!(collective.node_token_count(&user), 1);
+    assert_eq!(collective.node_tokens(&user, &0, &10), vec![&env, node]);
+    assert_eq!(collective.file_token_count(&user), 1);
+    assert_eq!(collective.file_tokens(&user, &0, &10), vec![&env, file.clone()]);
+
+    let second = collective.deploy_ipfs_token(
+        &user,
+        &String::from_val(&env, &"MyOtherFile"),
+        &String::from_val(&env, &"QmOther"),
+        &String::from_val(&env, &"image/png"),
+        &String::from_val(&env, &"https://ipfs.io"),
+        &None,
+    );
+    assert_eq!(collective.file_tokens(&user, &1, &10), vec![&env, second.clone()]);
+    assert_eq!(collective.file_tokens(&user, &0, &10), vec![&env, file, second]);
+}
+
+#[test]
+fn test_ban_appeal_and_unban() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let user = Address::generate(&env);
+    let (pay_token_client, pay_token_admin_client) = create_token_contract(&env, &admin);
+    pay_token_admin_client.mint(&user, &100);
+
+    let collective = CollectiveContractClient::new(
+        &env,
+        &env.register(CollectiveContract, (&admin, 10_i128, 5_i128, &pay_token_client.address, 3_i128))
+    );
+
+    collective.join(&user);
+    collective.ban(&admin, &user, &String::from_val(&env, &"QmReason"), &0);
+    assert!(collective.is_banned(&user));
+    assert!(!collective.is_member(&user));
+
+    let appeal = String::from_val(&env, &"QmAppeal");
+    collective.appeal(&user, &appeal);
+    assert_eq!(collective.ban_info(&user).unwrap().appeal, Some(appeal));
+
+    assert!(collective.unban(&admin, &user));
+    collective.join(&user);
+    assert!(collective.is_member(&user));
+}
+
+#[test]
+#[should_panic(expected = "banned")]
+fn test_banned_cannot_rejoin() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let user = Address::generate(&env);
+    let (pay_token_client, pay_token_admin_client) = create_token_contract(&env, &admin);
+    pay_token_admin_client.mint(&user, &100);
+
+    let collective = CollectiveContractClient::new(
+        &env,
+        &env.register(CollectiveContract, (&admin, 10_i128, 5_i128, &pay_token_client.address, 3_i128))
+    );
+
+    collective.join(&user);
+    collective.ban(&admin, &user, &String::from_val(&env, &"QmReason"), &1000);
+    collective.join(&user);
+}
+
+#[test]
+fn test_roles_grant_and_revoke() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let treasurer = Address::generate(&env);
+    let moderator = Address::generate(&env);
+    let user = Address::generate(&env);
+    let (pay_token_client, pay_token_admin_client) = create_token_contract(&env, &admin);
+    pay_token_admin_client.mint(&user, &100);
+
+    let collective = CollectiveContractClient::new(
+        &env,
+        &env.register(CollectiveContract, (&admin, 10_i128, 5_i128, &pay_token_client.address, 3_i128))
+    );
+
+    collective.grant_role(&TREASURER, &treasurer);
+    collective.grant_role(&MODERATOR, &moderator);
+    assert!(collective.has_role(&TREASURER, &treasurer));
+    assert!(!collective.has_role(&MODERATOR, &treasurer));
+
+    collective.join(&user);
+    assert!(collective.remove(&moderator, &user));
+
+    collective.withdraw(&treasurer, &treasurer);
+    assert_eq!(pay_token_client.balance(&treasurer), 10);
+
+    assert!(collective.revoke_role(&TREASURER, &treasurer));
+    assert!(!collective.has_role(&TREASURER, &treasurer));
+}
+
+#[test]
+#[should_panic(expected = "unauthorized")]
+fn test_withdraw_without_role() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let moderator = Address::generate(&env);
+    let (pay_token_client, pay_token_admin_client) = create_token_contract(&env, &admin);
+    pay_token_admin_client.mint(&moderator, &100);
+
+    let collective = CollectiveContractClient::new(
+        &env,
+        &env.register(CollectiveContract, (&admin, 10_i128, 5_i128, &pay_token_client.address, 3_i128))
+    );
+
+    collective.grant_role(&MODERATOR, &moderator);
+    collective.fund_contract(&moderator, &50);
+    collective.withdraw(&moderator, &moderator);
+}
+
+#[test]
+fn test_sponsor_join() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let sponsor = Address::generate(&env);
+    let artist = Address::generate(&env);
+    let (pay_token_client, pay_token_admin_client) = create_token_contract(&env, &admin);
+    pay_token_admin_client.mint(&sponsor, &100);
+
+    let collective = CollectiveContractClient::new(
+        &env,
+        &env.register(CollectiveContract, (&admin, 10_i128, 5_i128, &pay_token_client.address, 3_i128))
+    );
+
+    collective.sponsor_join(&sponsor, &artist, &symbol_short!("member"));
+    assert!(collective.is_member(&artist));
+    assert_eq!(pay_token_client.balance(&sponsor), 90);
+    assert_eq!(pay_token_client.balance(&artist), 0);
+}
+
+#[test]
+fn test_sponsor_pool_allocation() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let sponsor = Address::generate(&env);
+    let artist = Address::generate(&env);
+    let (pay_token_client, pay_token_admin_client) = create_token_contract(&env, &admin);
+    pay_token_admin_client.mint(&sponsor, &100);
+
+    let collective = CollectiveContractClient::new(
+        &env,
+        &env.register(CollectiveContract, (&admin, 10_i128, 5_i128, &pay_token_client.address, 3_i128))
+    );
+
+    let pool = collective.create_pool(&sponsor, &30);
+    assert_eq!(collective.fund_pool(&sponsor, &pool, &20), 50);
+    assert_eq!(collective.reserved(), 50);
+
+    assert_eq!(collective.allocate_pool(&admin, &pool, &artist, &symbol_short!("member")), 40);
+    assert!(collective.is_member(&artist));
+    assert_eq!(collective.reserved(), 40);
+
+    // only the allocated fee is withdrawable, the rest stays with the pool
+    collective.withdraw(&admin, &admin);
+    assert_eq!(pay_token_client.balance(&admin), 10);
+
+    assert_eq!(collective.close_pool(&sponsor, &pool), 40);
+    assert_eq!(pay_token_client.balance(&sponsor), 90);
+    assert_eq!(collective.reserved(), 0);
+}
+
+#[test]
+fn test_sponsored_member_leave_refunds_nothing() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let sponsor = Address::generate(&env);
+    let artist = Address::generate(&env);
+    let pooled = Address::generate(&env);
+    let (pay_token_client, pay_token_admin_client) = create_token_contract(&env, &admin);
+    pay_token_admin_client.mint(&sponsor, &100);
+
+    let collective = CollectiveContractClient::new(
+        &env,
+        &env.register(CollectiveContract, (&admin, 10_i128, 5_i128, &pay_token_client.address, 3_i128))
+    );
+
+    collective.set_refund_policy(&admin, &100, &0);
+    collective.sponsor_join(&sponsor, &artist, &symbol_short!("member"));
+    let pool = collective.create_pool(&sponsor, &10);
+    collective.allocate_pool(&admin, &pool, &pooled, &symbol_short!("member"));
+    assert_eq!(collective.member_paid(&artist), 0);
+    assert_eq!(collective.leave_refund(&pooled), 0);
+
+    assert_eq!(collective.leave(&artist), 0);
+    assert_eq!(collective.leave(&pooled), 0);
+    assert_eq!(pay_token_client.balance(&artist), 0);
+    assert_eq!(pay_token_client.balance(&pooled), 0);
+    assert_eq!(pay_token_client.balance(&collective.address), 20);
+}
+
+#[test]
+fn test_gated_applications() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let accepted = Address::generate(&env);
+    let rejected = Address::generate(&env);
+    let (pay_token_client, pay_token_admin_client) = create_token_contract(&env, &admin);
+    pay_token_admin_client.mint(&accepted, &100);
+    pay_token_admin_client.mint(&rejected, &100);
+
+    let collective = CollectiveContractClient::new(
+        &env,
+        &env.register(CollectiveContract, (&admin, 10_i128, 5_i128, &pay_token_client.address, 3_i128))
+    );
+
+    collective.set_join_mode(&JoinMode::Gated);
+    let portfolio = String::from_val(&env, &"QmPortfolio");
+    collective.apply(&accepted, &symbol_short!("member"), &portfolio);
+    collective.apply(&rejected, &symbol_short!("member"), &portfolio);
+    assert_eq!(collective.reserved(), 20);
+
+    collective.approve_application(&admin, &accepted);
+    assert!(collective.is_member(&accepted));
+    assert_eq!(collective.member_paid(&accepted), 10);
+
+    assert_eq!(collective.reject_application(&admin, &rejected), 10);
+    assert!(!collective.is_member(&rejected));
+    assert_eq!(pay_token_client.balance(&rejected), 100);
+    assert_eq!(collective.application(&rejected), None);
+    assert_eq!(collective.reserved(), 0);
+}
+
+#[test]
+#[should_panic(expected = "application required")]
+fn test_gated_mode_blocks_join() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let user = Address::generate(&env);
+    let (pay_token_client, pay_token_admin_client) = create_token_contract(&env, &admin);
+    pay_token_admin_client.mint(&user, &100);
+
+    let collective = CollectiveContractClient::new(
+        &env,
+        &env.register(CollectiveContract, (&admin, 10_i128, 5_i128, &pay_token_client.address, 3_i128))
+    );
+
+    collective.set_join_mode(&JoinMode::Gated);
+    collective.join(&user);
+}
+
+#[test]
+fn test_suspend_and_lapse() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let user = Address::generate(&env);
+    let (pay_token_client, pay_token_admin_client) = create_token_contract(&env, &admin);
+    pay_token_admin_client.mint(&user, &100);
+
+    let collective = CollectiveContractClient::new(
+        &env,
+        &env.register(CollectiveContract, (&admin, 10_i128, 5_i128, &pay_token_client.address, 3_i128))
+    );
+
+    collective.join(&user);
+    collective.suspend(&admin, &user, &100, &String::from_val(&env, &"QmReason"));
+    assert!(collective.is_suspended(&user));
+    assert!(!collective.is_member(&user));
+    assert_eq!(collective.member_paid(&user), 10);
+
+    env.ledger().with_mut(|li| li.sequence_number = 100);
+    assert!(!collective.is_suspended(&user));
+    assert!(collective.is_member(&user));
+
+    collective.suspend(&admin, &user, &200, &String::from_val(&env, &"QmReason"));
+    assert!(collective.reinstate(&admin, &user));
+    assert!(collective.is_member(&user));
+}
+
+#[test]
+#[should_panic(expected = "suspended")]
+fn test_suspended_cannot_publish() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let user = Address::generate(&env);
+    let (pay_token_client, pay_token_admin_client) = create_token_contract(&env, &admin);
+    pay_token_admin_client.mint(&user, &100);
+
+    let collective = CollectiveContractClient::new(
+        &env,
+        &env.register(CollectiveContract, (&admin, 10_i128, 5_i128, &pay_token_client.address, 3_i128))
+    );
+
+    collective.join(&user);
+    collective.suspend(&admin, &user, &100, &String::from_val(&env, &"QmReason"));
+    collective.publish_file(&user, &String::from_val(&env, &"SomeHash"));
+}
+
+fn sign_voucher(env: &Env, signing_key: &SigningKey, voucher: &Voucher) -> BytesN<64> {
+    let message = voucher.clone().to_xdr(env);
+    let mut buf = std::vec![0u8; message.len() as usize];
+    message.copy_into_slice(&mut buf);
+    BytesN::from_array(env, &signing_key.sign(&buf).to_bytes())
+}
+
+#[test]
+fn test_join_with_voucher() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let user = Address::generate(&env);
+    let (pay_token_client, pay_token_admin_client) = create_token_contract(&env, &admin);
+    pay_token_admin_client.mint(&user, &100);
+
+    let collective = CollectiveContractClient::new(
+        &env,
+        &env.register(CollectiveContract, (&admin, 20_i128, 5_i128, &pay_token_client.address, 3_i128))
+    );
+
+    let signing_key = SigningKey::from_bytes(&[7u8; 32]);
+    let signer = BytesN::from_array(&env, &signing_key.verifying_key().to_bytes());
+    collective.add_voucher_key(&admin, &signer);
+
+    let voucher = Voucher {
+        collective: collective.address.clone(),
+        recipient: user.clone(),
+        fee: symbol_short!("join"),
+        discount: 75,
+        expires: 1000,
+        nonce: 1,
+    };
+    let signature = sign_voucher(&env, &signing_key, &voucher);
+
+    assert_eq!(collective.join_with_voucher(&user, &symbol_short!("member"), &voucher, &signer, &signature), 5);
+    assert!(collective.is_member(&user));
+    assert_eq!(pay_token_client.balance(&user), 95);
+    assert!(collective.is_voucher_used(&signer, &1));
+}
+
+#[test]
+#[should_panic(expected = "voucher already used")]
+fn test_voucher_nonce_reuse() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let user = Address::generate(&env);
+    let (pay_token_client, pay_token_admin_client) = create_token_contract(&env, &admin);
+    pay_token_admin_client.mint(&user, &100);
+
+    let collective = CollectiveContractClient::new(
+        &env,
+        &env.register(CollectiveContract, (&admin, 20_i128, 5_i128, &pay_token_client.address, 3_i128))
+    );
+
+    let signing_key = SigningKey::from_bytes(&[7u8; 32]);
+    let signer = BytesN::from_array(&env, &signing_key.verifying_key().to_bytes());
+    collective.add_voucher_key(&admin, &signer);
+
+    let voucher = Voucher {
+        collective: collective.address.clone(),
+        recipient: user.clone(),
+        fee: symbol_short!("mint"),
+        discount: 100,
+        expires: 1000,
+        nonce: 1,
+    };
+    let signature = sign_voucher(&env, &signing_key, &voucher);
+
+    collective.join_with_voucher(&user, &symbol_short!("member"), &voucher, &signer, &signature);
+    assert_eq!(collective.mint_credit(&user), 100);
+
+    collective.leave(&user);
+    collective.join_with_voucher(&user, &symbol_short!("member"), &voucher, &signer, &signature);
+}
+
+#[test]
+fn test_treasury_report() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let user = Address::generate(&env);
+    let donor = Address::generate(&env);
+    let recipient = Address::generate(&env);
+    let (pay_token_client, pay_token_admin_client) = create_token_contract(&env, &admin);
+    pay_token_admin_client.mint(&user, &100);
+    pay_token_admin_client.mint(&donor, &100);
+
+    let collective = CollectiveContractClient::new(
+        &env,
+        &env.register(CollectiveContract, (&admin, 10_i128, 5_i128, &pay_token_client.address, 3_i128))
+    );
+
+    collective.join(&user);
+    collective.publish_file(&user, &String::from_val(&env, &"SomeHash"));
+    collective.publish_encrypted_share(&user, &donor, &String::from_val(&env, &"SomeHash"));
+    collective.fund_contract(&donor, &40);
+    collective.withdraw(&admin, &recipient);
+
+    let report = collective.treasury_report();
+    assert_eq!(report.joins, 10);
+    assert_eq!(report.publishes, 5);
+    assert_eq!(report.shares, 5);
+    assert_eq!(report.donations, 40);
+    assert_eq!(report.withdrawals, 60);
+    assert_eq!(report.balance, 0);
+
+    assert_eq!(collective.income(&INCOME_JOIN), 10);
+    assert_eq!(collective.income_from(&user), 20);
+    assert_eq!(collective.income_from(&donor), 40);
+    assert_eq!(collective.withdrawn_by(&recipient), 60);
+}
+
+#[test]
+fn test_withdraw_amount_and_distribute() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let donor = Address::generate(&env);
+    let operators = Address::generate(&env);
+    let artists = Address::generate(&env);
+    let infra = Address::generate(&env);
+    let (pay_token_client, pay_token_admin_client) = create_token_contract(&env, &admin);
+    pay_token_admin_client.mint(&donor, &1000);
+
+    let collective = CollectiveContractClient::new(
+        &env,
+        &env.register(CollectiveContract, (&admin, 10_i128, 5_i128, &pay_token_client.address, 3_i128))
+    );
+
+    collective.fund_contract(&donor, &1000);
+    assert_eq!(collective.withdraw_amount(&admin, &infra, &100), 100);
+    assert_eq!(pay_token_client.balance(&collective.address), 900);
+
+    collective.set_splits(&admin, &vec![
+        &env,
+        Split { recipient: operators.clone(), bps: 5000 },
+        Split { recipient: artists.clone(), bps: 3000 },
+        Split { recipient: infra.clone(), bps: 2000 },
+    ]);
+    assert_eq!(collective.distribute(&admin), 900);
+
+    let events = env.events().all();
+    assert_eq!(events.len(), 6);
+
+    assert_eq!(pay_token_client.balance(&operators), 450);
+    assert_eq!(pay_token_client.balance(&artists), 270);
+    assert_eq!(pay_token_client.balance(&infra), 280);
+    assert_eq!(collective.withdrawn_by(&infra), 280);
+}
+
+#[test]
+#[should_panic(expected = "splits must total 10000 bps")]
+fn test_invalid_splits() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let (pay_token_client, _) = create_token_contract(&env, &admin);
+
+    let collective = CollectiveContractClient::new(
+        &env,
+        &env.register(CollectiveContract, (&admin, 10_i128, 5_i128, &pay_token_client.address, 3_i128))
+    );
+
+    collective.set_splits(&admin, &vec![&env, Split { recipient: admin.clone(), bps: 9000 }]);
+}
+
+#[test]
+fn test_council_withdrawal() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let donor = Address::generate(&env);
+    let signer1 = Address::generate(&env);
+    let signer2 = Address::generate(&env);
+    let signer3 = Address::generate(&env);
+    let to = Address::generate(&env);
+    let (pay_token_client, pay_token_admin_client) = create_token_contract(&env, &admin);
+    pay_token_admin_client.mint(&donor, &1000);
+
+    let collective = CollectiveContractClient::new(
+        &env,
+        &env.register(CollectiveContract, (&admin, 10_i128, 5_i128, &pay_token_client.address, 3_i128))
+    );
+
+    collective.fund_contract(&donor, &1000);
+    collective.set_council(&vec![&env, signer1.clone(), signer2.clone(), signer3.clone()], &2, &100);
+
+    let id = collective.propose(&signer1, &CouncilAction::WithdrawAmount(to.clone(), 400));
+    let fee_id = collective.propose(&signer2, &CouncilAction::UpdateJoinFee(25));
+    assert!(collective.try_execute(&id).is_err());
+
+    assert_eq!(collective.approve(&signer3, &id), 2);
+    collective.execute(&id);
+    assert_eq!(pay_token_client.balance(&to), 400);
+    assert!(collective.proposal(&id).unwrap().executed);
+    assert!(collective.try_execute(&id).is_err());
+
+    collective.approve(&signer1, &fee_id);
+    collective.execute(&fee_id);
+    assert_eq!(collective.pending_fees().unwrap().join_fee, 25);
+}
+
+#[test]
+#[should_panic(expected = "council approval required")]
+fn test_council_blocks_direct_withdraw() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let signer = Address::generate(&env);
+    let (pay_token_client, _) = create_token_contract(&env, &admin);
+
+    let collective = CollectiveContractClient::new(
+        &env,
+        &env.register(CollectiveContract, (&admin, 10_i128, 5_i128, &pay_token_client.address, 3_i128))
+    );
+
+    collective.set_council(&vec![&env, signer.clone()], &1, &100);
+    collective.withdraw(&admin, &admin);
+}
+
+#[test]
+fn test_council_covers_member_and_admin_actions() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let member = Address::generate(&env);
+    let successor = Address::generate(&env);
+    let signer = Address::generate(&env);
+    let (pay_token_client, pay_token_admin_client) = create_token_contract(&env, &admin);
+    pay_token_admin_client.mint(&member, &100);
+
+    let collective = CollectiveContractClient::new(
+        &env,
+        &env.register(CollectiveContract, (&admin, 10_i128, 5_i128, &pay_token_client.address, 3_i128))
+    );
+
+    collective.join(&member);
+    collective.set_council(&vec![&env, signer.clone()], &1, &100);
+
+    let reason = String::from_val(&env, &"QmReason");
+    assert!(collective.try_ban(&admin, &member, &reason, &0).is_err());
+    assert!(collective.try_set_tier(&admin, &symbol_short!("patron"), &50, &10, &150, &ALL_ACTIONS).is_err());
+    assert!(collective.try_set_membership_term(&admin, &1000, &100, &5).is_err());
+    assert!(collective.try_propose_admin(&successor).is_err());
+
+    let tier = Tier {
+        name: symbol_short!("patron"),
+        join_fee: 50,
+        mint_discount: 10,
+        reward_multiplier: 150,
+        actions: ALL_ACTIONS,
+    };
+    let term = MembershipTerm { duration: 1000, grace: 100, renew_fee: 5 };
+    let actions = [
+        CouncilAction::Ban(member.clone(), reason, 0),
+        CouncilAction::SetTier(tier),
+        CouncilAction::SetTerm(term.clone()),
+        CouncilAction::ProposeAdmin(successor.clone()),
+    ];
+
+    for action in actions {
+        let id = collective.propose(&signer, &action);
+        collective.execute(&id);
+    }
+
+    assert!(collective.is_banned(&member));
+    assert!(!collective.is_member(&member));
+    assert_eq!(collective.tier(&symbol_short!("patron")).join_fee, 50);
+    assert_eq!(collective.membership_term(), term);
+    assert_eq!(collective.pending_admin(), Some(successor));
+}
+
+#[test]
+fn test_council_covers_settings() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let treasurer = Address::generate(&env);
+    let successor = Address::generate(&env);
+    let staking = Address::generate(&env);
+    let signer = Address::generate(&env);
+    let (pay_token_client, _) = create_token_contract(&env, &admin);
+
+    let collective = CollectiveContractClient::new(
+        &env,
+        &env.register(CollectiveContract, (&admin, 10_i128, 5_i128, &pay_token_client.address, 3_i128))
+    );
+
+    collective.set_tier(&admin, &symbol_short!("patron"), &50, &10, &150, &ALL_ACTIONS);
+    collective.propose_admin(&successor);
+    collective.set_council(&vec![&env, signer.clone()], &1, &100);
+
+    let key = BytesN::from_array(&env, &[7; 32]);
+    assert!(collective.try_grant_role(&TREASURER, &treasurer).is_err());
+    assert!(collective.try_revoke_role(&TREASURER, &treasurer).is_err());
+    assert!(collective.try_set_join_mode(&JoinMode::Gated).is_err());
+    assert!(collective.try_add_voucher_key(&admin, &key).is_err());
+    assert!(collective.try_set_referral_policy(&admin, &100, &0, &0).is_err());
+    assert!(collective.try_remove_tier(&admin, &symbol_short!("patron")).is_err());
+    assert!(collective.try_set_dispute_window(&admin, &100).is_err());
+    assert!(collective.try_set_refund_policy(&admin, &50, &0).is_err());
+    assert!(collective.try_set_staking(&staking).is_err());
+    assert!(collective.try_cancel_admin_proposal().is_err());
+
+    let actions = [
+        CouncilAction::GrantRole(TREASURER, treasurer.clone()),
+        CouncilAction::SetJoinMode(JoinMode::Gated),
+        CouncilAction::AddVoucherKey(key.clone()),
+        CouncilAction::SetReferralPolicy(ReferralPolicy { bonus: 100, cap: 0, period: 0 }),
+        CouncilAction::RemoveTier(symbol_short!("patron")),
+        CouncilAction::SetDisputeWindow(100),
+        CouncilAction::SetRefundPolicy(RefundPolicy { percent: 50, window: 0 }),
+        CouncilAction::SetStaking(staking.clone()),
+        CouncilAction::CancelAdminProposal,
+    ];
+
+    for action in actions {
+        let id = collective.propose(&signer, &action);
+        collective.execute(&id);
+    }
+
+    assert!(collective.has_role(&TREASURER, &treasurer));
+    assert_eq!(collective.join_mode(), JoinMode::Gated);
+    assert_eq!(collective.referral_policy().bonus, 100);
+    assert!(collective.pending_tier(&symbol_short!("patron")).unwrap().removed);
+    assert_eq!(collective.dispute_window(), 100);
+    assert_eq!(collective.refund_policy().percent, 50);
+    assert_eq!(collective.staking(), Some(staking));
+    assert_eq!(collective.pending_admin(), None);
+
+    // a leaked voucher key can still be revoked without waiting on the council
+    assert!(collective.remove_voucher_key(&admin, &key));
+
+    let id = collective.propose(&signer, &CouncilAction::RevokeRole(TREASURER, treasurer.clone()));
+    collective.execute(&id);
+    assert!(!collective.has_role(&TREASURER, &treasurer));
+}
+
+#[test]
+fn test_council_covers_vesting_grants() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let contributor = Address::generate(&env);
+    let signer = Address::generate(&env);
+    let (pay_token_client, _) = create_token_contract(&env, &admin);
+
+    let collective = CollectiveContractClient::new(
+        &env,
+        &env.register(CollectiveContract, (&admin, 10_i128, 5_i128, &pay_token_client.address, 3_i128))
+    );
+
+    collective.launch_opus(&admin, &1_000, &1_000_000, &1_000, &10_000);
+    assert!(collective.try_grant_vesting(&contributor, &contributor, &100, &0, &100, &true).is_err());
+
+    collective.set_council(&vec![&env, signer.clone()], &1, &100);
+    assert!(collective.try_grant_vesting(&admin, &contributor, &100, &0, &100, &true).is_err());
+
+    let id = collective.propose(&signer, &CouncilAction::GrantVesting(contributor.clone(), 100, 0, 100, true));
+    collective.execute(&id);
+    assert_eq!(collective.grant(&0).unwrap().amount, 100);
+    assert!(collective.try_revoke_grant(&admin, &0).is_err());
+
+    let id = collective.propose(&signer, &CouncilAction::RevokeGrant(0));
+    collective.execute(&id);
+    assert!(collective.grant(&0).unwrap().revoked);
+    assert_eq!(collective.granted(), 0);
+}
+
+#[test]
+#[should_panic(expected = "proposal expired")]
+fn test_council_proposal_expires() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let member = Address::generate(&env);
+    let signer1 = Address::generate(&env);
+    let signer2 = Address::generate(&env);
+    let (pay_token_client, _) = create_token_contract(&env, &admin);
+
+    let collective = CollectiveContractClient::new(
+        &env,
+        &env.register(CollectiveContract, (&admin, 10_i128, 5_i128, &pay_token_client.address, 3_i128))
+    );
+
+    collective.set_council(&vec![&env, signer1.clone(), signer2.clone()], &2, &10);
+    let id = collective.propose(&signer1, &CouncilAction::Remove(member));
+
+    env.ledger().with_mut(|li| li.sequence_number += 11);
+    collective.approve(&signer2, &id);
+}
+
+#[test]
+fn test_admin_transfer() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let typo = Address::generate(&env);
+    let new_admin = Address::generate(&env);
+    let (pay_token_client, _) = create_token_contract(&env, &admin);
+
+    let collective = CollectiveContractClient::new(
+        &env,
+        &env.register(CollectiveContract, (&admin, 10_i128, 5_i128, &pay_token_client.address, 3_i128))
+    );
+
+    collective.propose_admin(&typo);
+    collective.cancel_admin_proposal();
+    assert_eq!(collective.pending_admin(), None);
+    assert!(collective.try_accept_admin().is_err());
+
+    collective.propose_admin(&new_admin);
+    assert_eq!(collective.admin(), admin);
+
+    collective.accept_admin();
+    assert_eq!(env.auths()[0].0, new_admin);
+    assert_eq!(collective.admin(), new_admin);
+    assert_eq!(collective.pending_admin(), None);
+    assert!(collective.is_member(&new_admin));
+
+    collective.update_join_fee(&new_admin, &20);
+    assert_eq!(collective.pending_fees().unwrap().join_fee, 20);
+    assert!(collective.try_update_join_fee(&admin, &30).is_err());
+}
+
+#[test]
+fn test_full_precision_fees() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let user = Address::generate(&env);
+    let (pay_token_client, pay_token_admin_client) = create_token_contract(&env, &admin);
+    // 500 units of a 7 decimal token, well past what fits in a u32
+    let join_fee: i128 = 5_000_000_000;
+    pay_token_admin_client.mint(&user, &(join_fee * 2));
+
+    let collective = CollectiveContractClient::new(
+        &env,
+        &env.register(CollectiveContract, (&admin, join_fee, 1_000_000_i128, &pay_token_client.address, 10_000_000_i128))
+    );
+
+    collective.join(&user);
+    assert_eq!(collective.member_paid(&user), join_fee);
+    assert_eq!(collective.fund_contract(&user, &join_fee), join_fee);
+    assert_eq!(pay_token_client.balance(&collective.address), join_fee * 2);
+
+    assert!(collective.try_update_join_fee(&admin, &-1).is_err());
+    assert!(collective.try_update_mint_fee(&admin, &i128::MAX).is_err());
+    assert_eq!(collective.mint_fee(), 1_000_000);
+}
+
+#[test]
+fn test_legacy_collective_migration() {
+    let env = Env::default();
+    env.mock_all_auths();
+    keep_entries_alive(&env);
+    let admin = Address::generate(&env);
+    let (pay_token_client, _) = create_token_contract(&env, &admin);
+
+    let collective = CollectiveContractClient::new(
+        &env,
+        &env.register(CollectiveContract, (&admin, 10_i128, 5_i128, &pay_token_client.address, 3_i128))
+    );
+
+    env.as_contract(&collective.address, || {
+        let legacy = CollectiveV1 {
+            symbol: symbol_short!("HVYM"),
+            join_fee: 12,
+            mint_fee: 6,
+            pay_token: pay_token_client.address.clone(),
+            opus_reward: 2,
+        };
+        env.storage().persistent().set(&Datakey::Collective, &legacy);
+    });
+
+    assert_eq!(collective.join_fee(), 12);
+    assert_eq!(collective.mint_fee(), 6);
+    assert_eq!(collective.opus_reward(), 2);
+
+    collective.update_opus_reward(&admin, &20_000_000_000);
+    env.ledger().with_mut(|li| li.sequence_number += FEE_NOTICE);
+    assert_eq!(collective.join_fee(), 12);
+    assert_eq!(collective.opus_reward(), 20_000_000_000);
+}
+
+#[test]
+fn test_mint_fee_escrow_and_settle() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let user = Address::generate(&env);
+    let recipient = Address::generate(&env);
+    let (pay_token_client, pay_token_admin_client) = create_token_contract(&env, &admin);
+    pay_token_admin_client.mint(&user, &100);
+
+    let collective = CollectiveContractClient::new(
+        &env,
+        &env.register(CollectiveContract, (&admin, 10_i128, 5_i128, &pay_token_client.address, 3_i128))
+    );
+
+    collective.set_dispute_window(&admin, &100);
+    collective.join(&user);
+    collective.publish_file(&user, &String::from_val(&env, &"SomeHash"));
+
+    let escrow = collective.escrow(&0).unwrap();
+    assert_eq!(escrow.amount, 5);
+    assert_eq!(collective.reserved(), 5);
+    assert!(collective.try_settle(&0).is_err());
+
+    collective.withdraw(&admin, &recipient);
+    assert_eq!(pay_token_client.balance(&recipient), 10);
+
+    env.ledger().with_mut(|li| li.timestamp += 100);
+    assert!(collective.try_reject_publication(&admin, &0, &true).is_err());
+    assert_eq!(collective.settle(&0), 5);
+    assert_eq!(collective.escrow(&0), None);
+    assert_eq!(collective.reserved(), 0);
+    assert_eq!(collective.treasury_report().publishes, 5);
+}
+
+#[test]
+fn test_reject_publication() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let honest = Address::generate(&env);
+    let infringer = Address::generate(&env);
+    let (pay_token_client, pay_token_admin_client) = create_token_contract(&env, &admin);
+    pay_token_admin_client.mint(&honest, &100);
+    pay_token_admin_client.mint(&infringer, &100);
+
+    let collective = CollectiveContractClient::new(
+        &env,
+        &env.register(CollectiveContract, (&admin, 10_i128, 5_i128, &pay_token_client.address, 3_i128))
+    );
+
+    collective.set_dispute_window(&admin, &100);
+    collective.join(&honest);
+    collective.join(&infringer);
+    collective.publish_file(&honest, &String::from_val(&env, &"Mistake"));
+    collective.publish_file(&infringer, &String::from_val(&env, &"Pirated"));
+
+    assert_eq!(collective.reject_publication(&admin, &0, &true), 5);
+    assert_eq!(pay_token_client.balance(&honest), 90);
+
+    assert_eq!(collective.reject_publication(&admin, &1, &false), 5);
+    assert_eq!(pay_token_client.balance(&infringer), 85);
+    assert_eq!(collective.treasury_report().publishes, 10);
+    assert_eq!(collective.treasury_report().refunds, 5);
+    assert_eq!(collective.reserved(), 0);
+    assert!(collective.try_settle(&1).is_err());
+}
+
+#[test]
+fn test_dividend_claims() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let holder = Address::generate(&env);
+    let latecomer = Address::generate(&env);
+    let donor = Address::generate(&env);
+    let (pay_token_client, pay_token_admin_client) = create_token_contract(&env, &admin);
+    pay_token_admin_client.mint(&donor, &1000);
+
+    let collective = CollectiveContractClient::new(
+        &env,
+        &env.register(CollectiveContract, (&admin, 10_i128, 5_i128, &pay_token_client.address, 3_i128))
+    );
+
+    env.ledger().with_mut(|li| li.sequence_number = 10);
+    collective.fund_contract(&donor, &1000);
+    let opus_address = collective.launch_opus(&admin, &300, &1_000_000, &1_000, &10_000);
+    let opus_client = opus_token::Client::new(&env, &opus_address);
+    collective.grant_vesting(&admin, &admin, &200, &0, &1, &false);
+    collective.grant_vesting(&admin, &holder, &100, &0, &1, &false);
+    env.ledger().with_mut(|li| li.timestamp += 1);
+    collective.release(&admin, &0);
+    collective.release(&holder, &1);
+
+    env.ledger().with_mut(|li| li.sequence_number = 11);
+    let epoch = collective.declare_dividend(&admin, &600);
+    assert_eq!(collective.dividend(&epoch).unwrap().snapshot, 10);
+    assert_eq!(collective.reserved(), 600);
+
+    // tokens moved after the snapshot do not carry the dividend with them
+    opus_client.transfer(&holder, &latecomer, &100);
+    assert!(collective.try_claim_dividend(&latecomer, &epoch).is_err());
+
+    assert_eq!(collective.claim_dividend(&holder, &epoch), 200);
+    assert_eq!(collective.claim_dividend(&admin, &epoch), 400);
+    assert!(collective.dividend_claimed(&epoch, &holder));
+    assert!(collective.try_claim_dividend(&holder, &epoch).is_err());
+
+    assert_eq!(pay_token_client.balance(&holder), 200);
+    assert_eq!(collective.dividend(&epoch).unwrap().claimed, 600);
+    assert_eq!(collective.reserved(), 0);
+    assert_eq!(collective.treasury_report().dividends, 600);
+}
+
+#[test]
+fn test_stakers_claim_dividends() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let staker = Address::generate(&env);
+    let donor = Address::generate(&env);
+    let (pay_token_client, pay_token_admin_client) = create_token_contract(&env, &admin);
+    pay_token_admin_client.mint(&donor, &1000);
+
+    let collective = CollectiveContractClient::new(
+        &env,
+        &env.register(CollectiveContract, (&admin, 10_i128, 5_i128, &pay_token_client.address, 3_i128))
+    );
+
+    env.ledger().with_mut(|li| li.sequence_number = 10);
+    collective.fund_contract(&donor, &1000);
+    let opus_address = collective.launch_opus(&admin, &300, &1_000_000, &1_000, &10_000);
+    collective.grant_vesting(&admin, &admin, &200, &0, &1, &false);
+    collective.grant_vesting(&admin, &staker, &100, &0, &1, &false);
+    env.ledger().with_mut(|li| li.timestamp += 1);
+    collective.release(&admin, &0);
+    collective.release(&staker, &1);
+
+    let staking = opus_staking::Client::new(
+        &env,
+        &env.register(opus_staking::WASM, (&admin, &opus_address, 1_000_u32, 2_000_u32))
+    );
+    staking.set_lock_options(&vec![&env, opus_staking::LockOption { duration: 2_592_000, multiplier: 100 }]);
+    staking.fund(&admin, &20);
+    staking.stake(&staker, &80, &2_592_000);
+    collective.set_staking(&staking.address);
+
+    env.ledger().with_mut(|li| li.sequence_number = 11);
+    let epoch = collective.declare_dividend(&admin, &600);
+
+    // the staker's locked OPUS counts as theirs, not the staking contract's
+    assert_eq!(collective.claim_dividend(&staker, &epoch), 200);
+    assert_eq!(collective.claim_dividend(&admin, &epoch), 360);
+    assert!(collective.try_claim_dividend(&staking.address, &epoch).is_err());
+}
+
+#[soroban_sdk::contract]
+struct InflatedStaking;
+
+#[soroban_sdk::contractimpl]
+impl InflatedStaking {
+    pub fn staked_at(_e: Env, _account: Address, _ledger: u32) -> i128 {
+        1_000_000
+    }
+}
+
+#[test]
+fn test_dividend_claims_stay_within_dividend() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let holder = Address::generate(&env);
+    let donor = Address::generate(&env);
+    let (pay_token_client, pay_token_admin_client) = create_token_contract(&env, &admin);
+    pay_token_admin_client.mint(&donor, &1000);
+
+    let collective = CollectiveContractClient::new(
+        &env,
+        &env.register(CollectiveContract, (&admin, 10_i128, 5_i128, &pay_token_client.address, 3_i128))
+    );
+
+    env.ledger().with_mut(|li| li.sequence_number = 10);
+    collective.fund_contract(&donor, &1000);
+    collective.launch_opus(&admin, &300, &1_000_000, &1_000, &10_000);
+    collective.grant_vesting(&admin, &holder, &100, &0, &1, &false);
+    collective.grant_vesting(&admin, &admin, &200, &0, &1, &false);
+    env.ledger().with_mut(|li| li.timestamp += 1);
+    collective.release(&holder, &0);
+    collective.release(&admin, &1);
+
+    env.ledger().with_mut(|li| li.sequence_number = 11);
+    let before = collective.declare_dividend(&admin, &300);
+
+    // swapping the staking contract later does not change an open dividend
+    let inflated = env.register(InflatedStaking, ());
+    collective.set_staking(&inflated);
+    assert_eq!(collective.dividend(&before).unwrap().staking, None);
+    assert_eq!(collective.claim_dividend(&holder, &before), 100);
+
+    // and no claim can take more than the dividend holds
+    env.ledger().with_mut(|li| li.sequence_number = 12);
+    let after = collective.declare_dividend(&admin, &300);
+    assert!(collective.try_claim_dividend(&holder, &after).is_err());
+    assert_eq!(collective.reserved(), 500);
+}
+
+#[test]
+fn test_reclaim_unclaimed_dividend() {
+    let env = Env::default();
+    env.mock_all_auths();
+    keep_entries_alive(&env);
+    let admin = Address::generate(&env);
+    let holder = Address::generate(&env);
+    let pool = Address::generate(&env);
+    let donor = Address::generate(&env);
+    let (pay_token_client, pay_token_admin_client) = create_token_contract(&env, &admin);
+    pay_token_admin_client.mint(&donor, &1000);
+
+    let collective = CollectiveContractClient::new(
+        &env,
+        &env.register(CollectiveContract, (&admin, 10_i128, 5_i128, &pay_token_client.address, 3_i128))
+    );
+
+    env.ledger().with_mut(|li| li.sequence_number = 10);
+    collective.fund_contract(&donor, &1000);
+    collective.launch_opus(&admin, &300, &1_000_000, &1_000, &10_000);
+    collective.grant_vesting(&admin, &holder, &100, &0, &1, &false);
+    collective.grant_vesting(&admin, &pool, &200, &0, &1, &false);
+    env.ledger().with_mut(|li| li.timestamp += 1);
+    collective.release(&holder, &0);
+    collective.release(&pool, &1);
+
+    env.ledger().with_mut(|li| li.sequence_number = 11);
+    let epoch = collective.declare_dividend(&admin, &600);
+    assert_eq!(collective.dividend(&epoch).unwrap().deadline, 11 + DIVIDEND_WINDOW);
+    assert_eq!(collective.claim_dividend(&holder, &epoch), 200);
+    assert!(collective.try_reclaim_dividend(&epoch).is_err());
+
+    // the pool never claims its share
+    env.ledger().with_mut(|li| li.sequence_number += DIVIDEND_WINDOW + 1);
+    assert!(collective.try_claim_dividend(&pool, &epoch).is_err());
+    assert_eq!(collective.reclaim_dividend(&epoch), 400);
+    assert!(collective.try_reclaim_dividend(&epoch).is_err());
+
+    assert_eq!(collective.dividend(&epoch).unwrap().reclaimed, 400);
+    assert_eq!(collective.reserved(), 0);
+
+    let report = collective.treasury_report();
+    assert_eq!(report.dividends, 200);
+    assert_eq!(report.balance, 800);
+}
+
+#[test]
+fn test_vesting_release() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let founder = Address::generate(&env);
+    let (pay_token_client, _) = create_token_contract(&env, &admin);
+
+    let collective = CollectiveContractClient::new(
+        &env,
+        &env.register(CollectiveContract, (&admin, 10_i128, 5_i128, &pay_token_client.address, 3_i128))
+    );
+
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+    let opus_address = collective.launch_opus(&admin, &1_000, &1_000_000, &1_000, &10_000);
+    let opus_client = opus_token::Client::new(&env, &opus_address);
+
+    let id = collective.grant_vesting(&admin, &founder, &1_000, &100, &400, &false);
+    let grant = collective.grant(&id).unwrap();
+    assert_eq!(grant.cliff, 1_100);
+    assert_eq!(grant.end, 1_400);
+    assert_eq!(collective.grant_count(), 1);
+    assert_eq!(collective.granted(), 1_000);
+
+    // nothing before the cliff
+    env.ledger().with_mut(|li| li.timestamp = 1_099);
+    assert_eq!(collective.releasable(&id), 0);
+    assert!(collective.try_release(&founder, &id).is_err());
+
+    // linear from the start once the cliff has passed
+    env.ledger().with_mut(|li| li.timestamp = 1_100);
+    assert_eq!(collective.release(&founder, &id), 250);
+    assert_eq!(opus_client.balance(&founder), 250);
+
+    env.ledger().with_mut(|li| li.timestamp = 1_300);
+    assert_eq!(collective.releasable(&id), 500);
+    assert!(collective.try_release(&admin, &id).is_err());
+    assert_eq!(collective.release(&founder, &id), 500);
+
+    env.ledger().with_mut(|li| li.timestamp = 2_000);
+    assert_eq!(collective.release(&founder, &id), 250);
+    assert_eq!(opus_client.balance(&founder), 1_000);
+    assert_eq!(collective.grant(&id).unwrap().released, 1_000);
+    assert_eq!(collective.granted(), 0);
+    assert!(collective.try_release(&founder, &id).is_err());
+}
+
+#[test]
+fn test_revoke_grant() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let contributor = Address::generate(&env);
+    let (pay_token_client, _) = create_token_contract(&env, &admin);
+
+    let collective = CollectiveContractClient::new(
+        &env,
+        &env.register(CollectiveContract, (&admin, 10_i128, 5_i128, &pay_token_client.address, 3_i128))
+    );
+
+    let opus_address = collective.launch_opus(&admin, &1_000, &1_000_000, &1_000, &10_000);
+    let opus_client = opus_token::Client::new(&env, &opus_address);
+    let revocable = collective.grant_vesting(&admin, &contributor, &800, &0, &100, &true);
+    let fixed = collective.grant_vesting(&admin, &contributor, &200, &0, &100, &false);
+
+    // the whole allocation is spoken for
+    assert!(collective.try_grant_vesting(&admin, &contributor, &1, &0, &100, &true).is_err());
+
+    env.ledger().with_mut(|li| li.timestamp += 25);
+    assert_eq!(collective.revoke_grant(&admin, &revocable), 600);
+    assert!(collective.try_revoke_grant(&admin, &revocable).is_err());
+    assert!(collective.try_revoke_grant(&admin, &fixed).is_err());
+    assert_eq!(collective.granted(), 400);
+
+    // what vested before the revocation can still be released
+    env.ledger().with_mut(|li| li.timestamp += 75);
+    assert_eq!(collective.release(&contributor, &revocable), 200);
+    assert_eq!(collective.release(&contributor, &fixed), 200);
+    assert_eq!(opus_client.balance(&contributor), 400);
+    assert_eq!(opus_client.balance(&collective.address), 600);
+
+    // clawed back opus can be granted again
+    collective.grant_vesting(&admin, &admin, &600, &0, &100, &false);
+}
+
+#[test]
+fn test_publication_reward_paid_on_settle() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let user = Address::generate(&env);
+    let (pay_token_client, pay_token_admin_client) = create_token_contract(&env, &admin);
+    pay_token_admin_client.mint(&user, &100);
+
+    let collective = CollectiveContractClient::new(
+        &env,
+        &env.register(CollectiveContract, (&admin, 10_i128, 5_i128, &pay_token_client.address, 5_i128))
+    );
+
+    collective.join(&user);
+    collective.set_dispute_window(&admin, &100);
+    let opus_address = collective.launch_opus(&admin, &100, &1_000_000, &1_000, &10_000);
+    let opus_client = opus_token::Client::new(&env, &opus_address);
+
+    let file_type = String::from_val(&env, &"image/png");
+    let gateways = String::from_val(&env, &"https://ipfs.io");
+    let ipns_hash: Option<String> = None;
+
+    for hash in ["QmKept", "QmPirated"] {
+        let name = String::from_val(&env, &hash);
+        collective.deploy_ipfs_token(&user, &name, &String::from_val(&env, &hash), &file_type, &gateways, &ipns_hash);
+    }
+
+    assert_eq!(opus_client.balance(&user), 0);
+    assert_eq!(collective.escrow(&0).unwrap().reward, 5);
+
+    collective.reject_publication(&admin, &1, &false);
+    env.ledger().with_mut(|li| li.timestamp += 100);
+    collective.settle(&0);
+
+    assert_eq!(opus_client.balance(&user), 5);
+    assert_eq!(collective.opus_budget(), 9_995);
+}
+
+#[test]
+fn test_rewards_limited_by_emission() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let user = Address::generate(&env);
+    let (pay_token_client, pay_token_admin_client) = create_token_contract(&env, &admin);
+    pay_token_admin_client.mint(&user, &100);
+
+    let collective = CollectiveContractClient::new(
+        &env,
+        &env.register(CollectiveContract, (&admin, 10_i128, 5_i128, &pay_token_client.address, 5_i128))
+    );
+
+    collective.join(&user);
+    let opus_address = collective.launch_opus(&admin, &100, &1_000_000, &1_000, &7);
+    let opus_client = opus_token::Client::new(&env, &opus_address);
+    assert_eq!(collective.opus_budget(), 7);
+
+    let file_type = String::from_val(&env, &"image/png");
+    let gateways = String::from_val(&env, &"https://ipfs.io");
+    let ipns_hash: Option<String> = None;
+
+    for hash in ["QmOne", "QmTwo", "QmThree"] {
+        let name = String::from_val(&env, &hash);
+        collective.deploy_ipfs_token(&user, &name, &String::from_val(&env, &hash), &file_type, &gateways, &ipns_hash);
+    }
+
+    assert_eq!(opus_client.balance(&user), 7);
+    assert_eq!(collective.opus_budget(), 0);
+
+    env.ledger().with_mut(|li| li.sequence_number += 1_000);
+    assert_eq!(collective.opus_budget(), 3);
+}
@@ -0,0 +1,355 @@
+#![no_std]
+use soroban_sdk::{
+    contract, contractimpl, contracttype, symbol_short, token, Address, Env, IntoVal, Symbol, TryFromVal, Val, Vec,
+};
+
+const ADMIN: Symbol = symbol_short!("admin");
+const STAKE: Symbol = symbol_short!("STAKE");
+
+const MAX_BPS: u32 = 10_000;
+const YEAR: u64 = 31_536_000;
+
+const DAY_IN_LEDGERS: u32 = 17280;
+const INSTANCE_BUMP_AMOUNT: u32 = 7 * DAY_IN_LEDGERS;
+const INSTANCE_LIFETIME_THRESHOLD: u32 = INSTANCE_BUMP_AMOUNT - DAY_IN_LEDGERS;
+
+const BALANCE_BUMP_AMOUNT: u32 = 30 * DAY_IN_LEDGERS;
+const BALANCE_LIFETIME_THRESHOLD: u32 = BALANCE_BUMP_AMOUNT - DAY_IN_LEDGERS;
+
+const LEDGER_SECONDS: u64 = 5;
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Datakey {
+    Config,
+    LockOptions,
+    Pool,
+    TotalStaked,
+    Staked(Address),
+    Stake(Address, u32),
+    StakeCount(Address),
+    Checkpoint(Address, u32),
+    CheckpointCount(Address),
+}
+
+/// `rate` is the yearly reward in basis points for a 1x lock, `penalty` the
+/// share of principal kept when unstaking early, also in basis points.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Config {
+    pub token: Address,
+    pub rate: u32,
+    pub penalty: u32,
+}
+
+/// A lock duration members can choose, in seconds, and the percentage of
+/// the base rate it earns (100 = 1x).
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LockOption {
+    pub duration: u64,
+    pub multiplier: u32,
+}
+
+/// A single lock. Its `reward` is set aside from the pool when staking, so a
+/// matured stake can always be paid out, and its early exit `penalty` is
+/// fixed when staking so later rate changes do not touch it.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Stake {
+    pub amount: i128,
+    pub reward: i128,
+    pub penalty: u32,
+    pub start: u64,
+    pub unlock: u64,
+}
+
+/// An account's staked total from ledger `ledger` on.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Checkpoint {
+    pub ledger: u32,
+    pub amount: i128,
+}
+
+#[contract]
+pub struct StakingContract;
+
+#[contractimpl]
+impl StakingContract {
+
+    pub fn __constructor(e: Env, admin: Address, token: Address, rate: u32, penalty: u32) {
+        if penalty > MAX_BPS {
+            panic!("penalty must not exceed 10000 bps");
+        }
+
+        e.storage().instance().set(&ADMIN, &admin);
+        write(&e, &Datakey::Config, &Config { token, rate, penalty });
+    }
+
+    pub fn set_rates(e: Env, rate: u32, penalty: u32) -> Config {
+        let admin: Address = e.storage().instance().get(&ADMIN).unwrap();
+        admin.require_auth();
+        bump_instance(&e);
+
+        if penalty > MAX_BPS {
+            panic!("penalty must not exceed 10000 bps");
+        }
+
+        let mut config = read_config(&e);
+        config.rate = rate;
+        config.penalty = penalty;
+        write(&e, &Datakey::Config, &config);
+
+        config
+    }
+
+    pub fn config(e: Env) -> Config {
+        read_config(&e)
+    }
+
+    pub fn set_lock_options(e: Env, options: Vec<LockOption>) {
+        let admin: Address = e.storage().instance().get(&ADMIN).unwrap();
+        admin.require_auth();
+        bump_instance(&e);
+
+        for option in options.iter() {
+            if option.duration == 0 {
+                panic!("lock duration must be positive");
+            }
+        }
+
+        write(&e, &Datakey::LockOptions, &options);
+    }
+
+    pub fn lock_options(e: Env) -> Vec<LockOption> {
+        read(&e, &Datakey::LockOptions).unwrap_or(Vec::new(&e))
+    }
+
+    /// Adds OPUS to the reward pool. Anyone may fund it.
+    pub fn fund(e: Env, funder: Address, amount: i128) -> i128 {
+        funder.require_auth();
+        bump_instance(&e);
+
+        if amount <= 0 {
+            panic!("amount must be positive");
+        }
+
+        let config = read_config(&e);
+        token::Client::new(&e, &config.token).transfer(&funder, &e.current_contract_address(), &amount);
+
+        let pool = read_pool(&e) + amount;
+        write(&e, &Datakey::Pool, &pool);
+        e.events().publish((STAKE, symbol_short!("fund")), (funder, amount));
+
+        pool
+    }
+
+    /// Rewards still available to new stakes.
+    pub fn pool(e: Env) -> i128 {
+        read_pool(&e)
+    }
+
+    /// Locks `amount` for one of the configured durations and returns the
+    /// stake id.
+    pub fn stake(e: Env, staker: Address, amount: i128, duration: u64) -> u32 {
+        staker.require_auth();
+        bump_instance(&e);
+
+        if amount <= 0 {
+            panic!("amount must be positive");
+        }
+
+        let option = Self::lock_options(e.clone())
+            .iter()
+            .find(|option| option.duration == duration)
+            .expect("unknown lock duration");
+        let config = read_config(&e);
+        let reward = amount
+            .checked_mul(config.rate as i128 * option.multiplier as i128 * duration as i128)
+            .expect("amount overflow")
+            / (MAX_BPS as i128 * 100 * YEAR as i128);
+        let pool = read_pool(&e);
+
+        if reward > pool {
+            panic!("reward pool exhausted");
+        }
+
+        token::Client::new(&e, &config.token).transfer(&staker, &e.current_contract_address(), &amount);
+
+        let now = e.ledger().timestamp();
+        let id: u32 = read(&e, &Datakey::StakeCount(staker.clone())).unwrap_or(0);
+        let stake = Stake { amount, reward, penalty: config.penalty, start: now, unlock: now + duration };
+
+        write(&e, &Datakey::Pool, &(pool - reward));
+        write(&e, &Datakey::Stake(staker.clone(), id), &stake);
+        write(&e, &Datakey::StakeCount(staker.clone()), &(id + 1));
+        add_staked(&e, &staker, amount);
+        keep_until_unlock(&e, &staker, id, duration);
+        e.events().publish((STAKE, symbol_short!("stake")), (staker, id, amount, stake.unlock));
+
+        id
+    }
+
+    /// Returns the principal and reward of a matured stake. Unstaking early
+    /// forfeits the reward and the penalty share of the principal, both of
+    /// which go back to the pool.
+    pub fn unstake(e: Env, staker: Address, id: u32) -> i128 {
+        staker.require_auth();
+        bump_instance(&e);
+
+        let key = Datakey::Stake(staker.clone(), id);
+        let stake: Stake = read(&e, &key).expect("no stake");
+        let config = read_config(&e);
+
+        let payout = if e.ledger().timestamp() >= stake.unlock {
+            stake.amount + stake.reward
+        } else {
+            let penalty = stake.amount * stake.penalty as i128 / MAX_BPS as i128;
+            let pool = read_pool(&e) + stake.reward + penalty;
+            write(&e, &Datakey::Pool, &pool);
+            stake.amount - penalty
+        };
+
+        e.storage().persistent().remove(&key);
+        add_staked(&e, &staker, -stake.amount);
+
+        if payout > 0 {
+            token::Client::new(&e, &config.token).transfer(&e.current_contract_address(), &staker, &payout);
+        }
+
+        e.events().publish((STAKE, symbol_short!("unstake")), (staker, id, payout));
+        payout
+    }
+
+    pub fn stake_info(e: Env, staker: Address, id: u32) -> Option<Stake> {
+        read(&e, &Datakey::Stake(staker, id))
+    }
+
+    pub fn stake_count(e: Env, staker: Address) -> u32 {
+        read(&e, &Datakey::StakeCount(staker)).unwrap_or(0)
+    }
+
+    /// OPUS currently locked by `account` across all of its stakes.
+    pub fn staked(e: Env, account: Address) -> i128 {
+        bump_instance(&e);
+        read(&e, &Datakey::Staked(account)).unwrap_or(0)
+    }
+
+    pub fn total_staked(e: Env) -> i128 {
+        read(&e, &Datakey::TotalStaked).unwrap_or(0)
+    }
+
+    /// OPUS `account` had staked at the close of a past `ledger`, so holders
+    /// keep their share of balance snapshots while their tokens are locked.
+    pub fn staked_at(e: Env, account: Address, ledger: u32) -> i128 {
+        bump_instance(&e);
+
+        if ledger >= e.ledger().sequence() {
+            panic!("ledger not yet closed");
+        }
+
+        let mut low = 0;
+        let mut high: u32 = read(&e, &Datakey::CheckpointCount(account.clone())).unwrap_or(0);
+
+        while low < high {
+            let mid = low + (high - low) / 2;
+            if read_checkpoint(&e, &account, mid).ledger <= ledger {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+
+        if low == 0 {
+            0
+        } else {
+            read_checkpoint(&e, &account, low - 1).amount
+        }
+    }
+}
+
+fn bump_instance(e: &Env) {
+    e.storage()
+        .instance()
+        .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+}
+
+fn bump(e: &Env, key: &Datakey) {
+    e.storage()
+        .persistent()
+        .extend_ttl(key, BALANCE_LIFETIME_THRESHOLD, BALANCE_BUMP_AMOUNT);
+}
+
+// Entries are kept alive whenever they are read or written.
+fn read<V: TryFromVal<Env, Val>>(e: &Env, key: &Datakey) -> Option<V> {
+    let value = e.storage().persistent().get(key);
+    if value.is_some() {
+        bump(e, key);
+    }
+    value
+}
+
+fn write<V: IntoVal<Env, Val>>(e: &Env, key: &Datakey, value: &V) {
+    e.storage().persistent().set(key, value);
+    bump(e, key);
+}
+
+// Extends everything unstaking touches for the staker past the end of the
+// lock, so a stake nobody looks at still pays out without a restore.
+fn keep_until_unlock(e: &Env, staker: &Address, id: u32, duration: u64) {
+    let ledgers = (duration / LEDGER_SECONDS).min(u32::MAX as u64) as u32;
+    let extend_to = ledgers.saturating_add(BALANCE_BUMP_AMOUNT);
+    let count: u32 = read(e, &Datakey::CheckpointCount(staker.clone())).unwrap_or(0);
+    let keys = [
+        Datakey::Stake(staker.clone(), id),
+        Datakey::Staked(staker.clone()),
+        Datakey::StakeCount(staker.clone()),
+        Datakey::CheckpointCount(staker.clone()),
+        Datakey::Checkpoint(staker.clone(), count - 1),
+    ];
+
+    for key in keys.iter() {
+        e.storage().persistent().extend_ttl(key, extend_to, extend_to);
+    }
+}
+
+fn read_config(e: &Env) -> Config {
+    read(e, &Datakey::Config).unwrap()
+}
+
+fn read_pool(e: &Env) -> i128 {
+    read(e, &Datakey::Pool).unwrap_or(0)
+}
+
+fn add_staked(e: &Env, account: &Address, amount: i128) {
+    let staked: i128 = read(e, &Datakey::Staked(account.clone())).unwrap_or(0);
+    let total: i128 = read(e, &Datakey::TotalStaked).unwrap_or(0);
+
+    write(e, &Datakey::Staked(account.clone()), &(staked + amount));
+    write(e, &Datakey::TotalStaked, &(total + amount));
+    write_checkpoint(e, account, staked + amount);
+}
+
+fn read_checkpoint(e: &Env, account: &Address, index: u32) -> Checkpoint {
+    let key = Datakey::Checkpoint(account.clone(), index);
+    read(e, &key).unwrap()
+}
+
+/// Several changes within one ledger share a single checkpoint.
+fn write_checkpoint(e: &Env, account: &Address, amount: i128) {
+    let ledger = e.ledger().sequence();
+    let count_key = Datakey::CheckpointCount(account.clone());
+    let count: u32 = read(e, &count_key).unwrap_or(0);
+
+    let index = if count > 0 && read_checkpoint(e, account, count - 1).ledger == ledger {
+        count - 1
+    } else {
+        write(e, &count_key, &(count + 1));
+        count
+    };
+
+    write(e, &Datakey::Checkpoint(account.clone(), index), &Checkpoint { ledger, amount });
+}
+
+mod test;
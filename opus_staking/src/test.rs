@@ -0,0 +1,193 @@
+#![cfg(test)]
+extern crate std;
+
+use crate::{
+    Datakey, LockOption, StakingContract, StakingContractClient, BALANCE_BUMP_AMOUNT,
+    INSTANCE_BUMP_AMOUNT,
+};
+use soroban_sdk::{
+    testutils::{storage::{Instance as _, Persistent as _}, Address as _, Ledger},
+    token, vec, Address, Env,
+};
+
+const MONTH: u64 = 2_592_000;
+const YEAR: u64 = 31_536_000;
+
+fn setup<'a>(e: &Env) -> (StakingContractClient<'a>, token::Client<'a>) {
+    e.mock_all_auths();
+
+    let admin = Address::generate(e);
+    let sac = e.register_stellar_asset_contract_v2(admin.clone());
+    let token = token::Client::new(e, &sac.address());
+    let minter = token::StellarAssetClient::new(e, &sac.address());
+
+    // 10% a year at 1x, 20% early exit penalty.
+    let staking = StakingContractClient::new(
+        e,
+        &e.register(StakingContract, (&admin, &sac.address(), 1_000_u32, 2_000_u32)),
+    );
+    staking.set_lock_options(&vec![
+        e,
+        LockOption { duration: MONTH, multiplier: 100 },
+        LockOption { duration: YEAR, multiplier: 200 },
+    ]);
+
+    minter.mint(&admin, &1_000_000);
+    staking.fund(&admin, &10_000);
+
+    (staking, token)
+}
+
+#[test]
+fn test_stake_and_unstake_after_expiry() {
+    let e = Env::default();
+    let (staking, token) = setup(&e);
+    let staker = Address::generate(&e);
+    token::StellarAssetClient::new(&e, &token.address).mint(&staker, &10_000);
+
+    // A year at 2x earns twice the base rate.
+    let id = staking.stake(&staker, &10_000, &YEAR);
+    let stake = staking.stake_info(&staker, &id).unwrap();
+    assert_eq!(stake.reward, 2_000);
+    assert_eq!(stake.unlock, stake.start + YEAR);
+    assert_eq!(staking.pool(), 8_000);
+    assert_eq!(staking.staked(&staker), 10_000);
+    assert_eq!(staking.total_staked(), 10_000);
+    assert_eq!(token.balance(&staker), 0);
+
+    e.ledger().with_mut(|l| l.timestamp += YEAR);
+
+    assert_eq!(staking.unstake(&staker, &id), 12_000);
+    assert_eq!(token.balance(&staker), 12_000);
+    assert_eq!(staking.staked(&staker), 0);
+    assert_eq!(staking.total_staked(), 0);
+    assert!(staking.stake_info(&staker, &id).is_none());
+}
+
+#[test]
+fn test_staked_at_follows_stakes() {
+    let e = Env::default();
+    let (staking, token) = setup(&e);
+    let staker = Address::generate(&e);
+    token::StellarAssetClient::new(&e, &token.address).mint(&staker, &3_000);
+
+    e.ledger().with_mut(|l| l.sequence_number = 10);
+    staking.stake(&staker, &1_000, &MONTH);
+    let id = staking.stake(&staker, &2_000, &MONTH);
+
+    e.ledger().with_mut(|l| {
+        l.sequence_number = 20;
+        l.timestamp += MONTH;
+    });
+    staking.unstake(&staker, &id);
+
+    e.ledger().with_mut(|l| l.sequence_number = 30);
+    assert_eq!(staking.staked_at(&staker, &9), 0);
+    assert_eq!(staking.staked_at(&staker, &10), 3_000);
+    assert_eq!(staking.staked_at(&staker, &19), 3_000);
+    assert_eq!(staking.staked_at(&staker, &20), 1_000);
+    assert!(staking.try_staked_at(&staker, &30).is_err());
+
+    // lookups keep the checkpoints they read alive
+    e.ledger().with_mut(|l| l.sequence_number += 2 * 17_280);
+    let checkpoint = Datakey::Checkpoint(staker.clone(), 1);
+    let ttl = e.as_contract(&staking.address, || e.storage().persistent().get_ttl(&checkpoint));
+    assert_eq!(staking.staked_at(&staker, &25), 1_000);
+    assert!(e.as_contract(&staking.address, || e.storage().persistent().get_ttl(&checkpoint)) > ttl);
+}
+
+#[test]
+fn test_stake_entries_stay_alive() {
+    let e = Env::default();
+    let (staking, token) = setup(&e);
+    let staker = Address::generate(&e);
+    token::StellarAssetClient::new(&e, &token.address).mint(&staker, &1_000);
+
+    let id = staking.stake(&staker, &1_000, &YEAR);
+
+    // the staker's own entries outlive the lock
+    let lock = (YEAR / 5) as u32;
+    e.as_contract(&staking.address, || {
+        let storage = e.storage().persistent();
+        assert!(storage.get_ttl(&Datakey::Stake(staker.clone(), id)) > lock);
+        assert!(storage.get_ttl(&Datakey::Staked(staker.clone())) > lock);
+        assert!(storage.get_ttl(&Datakey::StakeCount(staker.clone())) > lock);
+        assert!(storage.get_ttl(&Datakey::CheckpointCount(staker.clone())) > lock);
+        assert!(storage.get_ttl(&Datakey::Checkpoint(staker.clone(), 0)) > lock);
+        assert_eq!(storage.get_ttl(&Datakey::Pool), BALANCE_BUMP_AMOUNT);
+        assert_eq!(e.storage().instance().get_ttl(), INSTANCE_BUMP_AMOUNT);
+    });
+}
+
+#[test]
+fn test_longer_locks_earn_more() {
+    let e = Env::default();
+    let (staking, token) = setup(&e);
+    let staker = Address::generate(&e);
+    token::StellarAssetClient::new(&e, &token.address).mint(&staker, &24_000);
+
+    let short = staking.stake(&staker, &12_000, &MONTH);
+    let long = staking.stake(&staker, &12_000, &YEAR);
+
+    assert_eq!(staking.stake_count(&staker), 2);
+    assert_eq!(staking.stake_info(&staker, &short).unwrap().reward, 98);
+    assert_eq!(staking.stake_info(&staker, &long).unwrap().reward, 2_400);
+    assert_eq!(staking.staked(&staker), 24_000);
+}
+
+#[test]
+fn test_unstake_early_penalty() {
+    let e = Env::default();
+    let (staking, token) = setup(&e);
+    let staker = Address::generate(&e);
+    token::StellarAssetClient::new(&e, &token.address).mint(&staker, &10_000);
+
+    let id = staking.stake(&staker, &10_000, &YEAR);
+    e.ledger().with_mut(|l| l.timestamp += MONTH);
+
+    // Raising the penalty later does not apply to existing stakes.
+    staking.set_rates(&1_000, &10_000);
+    assert_eq!(staking.stake_info(&staker, &id).unwrap().penalty, 2_000);
+
+    // The reward and the 20% penalty go back to the pool.
+    assert_eq!(staking.unstake(&staker, &id), 8_000);
+    assert_eq!(token.balance(&staker), 8_000);
+    assert_eq!(staking.pool(), 12_000);
+    assert_eq!(staking.staked(&staker), 0);
+}
+
+#[test]
+#[should_panic(expected = "unknown lock duration")]
+fn test_stake_unknown_duration() {
+    let e = Env::default();
+    let (staking, token) = setup(&e);
+    let staker = Address::generate(&e);
+    token::StellarAssetClient::new(&e, &token.address).mint(&staker, &10_000);
+
+    staking.stake(&staker, &10_000, &(MONTH * 2));
+}
+
+#[test]
+#[should_panic(expected = "reward pool exhausted")]
+fn test_stake_pool_exhausted() {
+    let e = Env::default();
+    let (staking, token) = setup(&e);
+    let staker = Address::generate(&e);
+    token::StellarAssetClient::new(&e, &token.address).mint(&staker, &100_000);
+
+    staking.stake(&staker, &100_000, &YEAR);
+}
+
+#[test]
+#[should_panic(expected = "no stake")]
+fn test_unstake_twice() {
+    let e = Env::default();
+    let (staking, token) = setup(&e);
+    let staker = Address::generate(&e);
+    token::StellarAssetClient::new(&e, &token.address).mint(&staker, &1_000);
+
+    let id = staking.stake(&staker, &1_000, &MONTH);
+    e.ledger().with_mut(|l| l.timestamp += MONTH);
+    staking.unstake(&staker, &id);
+    staking.unstake(&staker, &id);
+}
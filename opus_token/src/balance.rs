@@ -1,4 +1,8 @@
-use crate::storage_types::{DataKey, BALANCE_BUMP_AMOUNT, BALANCE_LIFETIME_THRESHOLD};
+use crate::checkpoint::write_checkpoint;
+use crate::delegation::{add_votes, read_delegate};
+use crate::storage_types::{
+    CheckpointSeries, DataKey, BALANCE_BUMP_AMOUNT, BALANCE_LIFETIME_THRESHOLD,
+};
 use soroban_sdk::{Address, Env};
 
 pub fn read_balance(e: &Env, addr: Address) -> i128 {
@@ -14,15 +18,17 @@ pub fn read_balance(e: &Env, addr: Address) -> i128 {
 }
 
 fn write_balance(e: &Env, addr: Address, amount: i128) {
-    let key = DataKey::Balance(addr);
+    let key = DataKey::Balance(addr.clone());
     e.storage().persistent().set(&key, &amount);
     e.storage()
         .persistent()
         .extend_ttl(&key, BALANCE_LIFETIME_THRESHOLD, BALANCE_BUMP_AMOUNT);
+    write_checkpoint(e, CheckpointSeries::Balance(addr), amount);
 }
 
 pub fn receive_balance(e: &Env, addr: Address, amount: i128) {
     let balance = read_balance(e, addr.clone());
+    add_votes(e, &read_delegate(e, &addr), amount);
     write_balance(e, addr, balance + amount);
 }
 
@@ -31,5 +37,27 @@ pub fn spend_balance(e: &Env, addr: Address, amount: i128) {
     if balance < amount {
         panic!("insufficient balance");
     }
+    add_votes(e, &read_delegate(e, &addr), -amount);
     write_balance(e, addr, balance - amount);
 }
+
+pub fn read_total_supply(e: &Env) -> i128 {
+    let key = DataKey::TotalSupply;
+    e.storage().instance().get(&key).unwrap_or(0)
+}
+
+fn write_total_supply(e: &Env, amount: i128) {
+    let key = DataKey::TotalSupply;
+    e.storage().instance().set(&key, &amount);
+    write_checkpoint(e, CheckpointSeries::Supply, amount);
+}
+
+pub fn increase_supply(e: &Env, amount: i128) {
+    let supply = read_total_supply(e);
+    write_total_supply(e, supply.checked_add(amount).expect("supply overflow"));
+}
+
+pub fn decrease_supply(e: &Env, amount: i128) {
+    let supply = read_total_supply(e);
+    write_total_supply(e, supply - amount);
+}
@@ -0,0 +1,87 @@
+//! Historical values kept as one persistent entry per change. Entries are
+//! only kept alive while they are written or read, and a lookup reads just
+//! the checkpoints its binary search passes through, so checkpoints nobody
+//! has queried for a while get archived. Any historical query that lands on
+//! an archived checkpoint fails until the entry is restored, which callers
+//! do by submitting a footprint restore for the keys before retrying.
+use crate::storage_types::{
+    Checkpoint, CheckpointSeries, DataKey, BALANCE_BUMP_AMOUNT, BALANCE_LIFETIME_THRESHOLD,
+};
+use soroban_sdk::Env;
+
+/// Every lookup goes through the count, so reading it keeps it alive.
+pub fn read_checkpoint_count(e: &Env, series: &CheckpointSeries) -> u32 {
+    let key = DataKey::CheckpointCount(series.clone());
+    match e.storage().persistent().get(&key) {
+        Some(count) => {
+            e.storage()
+                .persistent()
+                .extend_ttl(&key, BALANCE_LIFETIME_THRESHOLD, BALANCE_BUMP_AMOUNT);
+            count
+        }
+        None => 0,
+    }
+}
+
+/// Old checkpoints are only ever read, so reading one keeps it alive for as
+/// long as historical queries touch it. Checkpoints that are never touched
+/// still expire, see the module docs.
+fn read_checkpoint(e: &Env, series: &CheckpointSeries, index: u32) -> Checkpoint {
+    let key = DataKey::Checkpoint(series.clone(), index);
+    let checkpoint = e.storage().persistent().get(&key).unwrap();
+    e.storage()
+        .persistent()
+        .extend_ttl(&key, BALANCE_LIFETIME_THRESHOLD, BALANCE_BUMP_AMOUNT);
+    checkpoint
+}
+
+/// Records `amount` as the value of `series` from the current ledger on.
+/// Several changes within one ledger share a single checkpoint.
+pub fn write_checkpoint(e: &Env, series: CheckpointSeries, amount: i128) {
+    let ledger = e.ledger().sequence();
+    let count = read_checkpoint_count(e, &series);
+
+    let index = if count > 0 && read_checkpoint(e, &series, count - 1).ledger == ledger {
+        count - 1
+    } else {
+        let count_key = DataKey::CheckpointCount(series.clone());
+        e.storage().persistent().set(&count_key, &(count + 1));
+        e.storage()
+            .persistent()
+            .extend_ttl(&count_key, BALANCE_LIFETIME_THRESHOLD, BALANCE_BUMP_AMOUNT);
+        count
+    };
+
+    let key = DataKey::Checkpoint(series, index);
+    e.storage().persistent().set(&key, &Checkpoint { ledger, amount });
+    e.storage()
+        .persistent()
+        .extend_ttl(&key, BALANCE_LIFETIME_THRESHOLD, BALANCE_BUMP_AMOUNT);
+}
+
+/// Value of `series` at the close of `ledger`, found by binary search over
+/// its checkpoints. Only past ledgers can be queried, since the current one
+/// may still change.
+pub fn read_checkpoint_at(e: &Env, series: CheckpointSeries, ledger: u32) -> i128 {
+    if ledger >= e.ledger().sequence() {
+        panic!("ledger not yet closed");
+    }
+
+    let mut low = 0;
+    let mut high = read_checkpoint_count(e, &series);
+
+    while low < high {
+        let mid = low + (high - low) / 2;
+        if read_checkpoint(e, &series, mid).ledger <= ledger {
+            low = mid + 1;
+        } else {
+            high = mid;
+        }
+    }
+
+    if low == 0 {
+        0
+    } else {
+        read_checkpoint(e, &series, low - 1).amount
+    }
+}
@@ -1,14 +1,28 @@
 //! This contract demonstrates a sample implementation of the Soroban token
 //! interface.
-use crate::admin::{read_administrator, write_administrator};
+use crate::admin::{
+    read_administrator, read_pending_administrator, remove_pending_administrator,
+    write_administrator, write_pending_administrator,
+};
 use crate::allowance::{read_allowance, spend_allowance, write_allowance};
-use crate::balance::{read_balance, receive_balance, spend_balance};
+use crate::balance::{
+    decrease_supply, increase_supply, read_balance, read_total_supply, receive_balance,
+    spend_balance,
+};
+use crate::checkpoint::read_checkpoint_at;
+use crate::delegation::{add_votes, read_delegate, read_votes, write_delegate};
+use crate::emission::{
+    current_epoch, read_emission, read_total_minted, remaining_emission, spend_cap,
+    spend_emission, write_emission,
+};
 use crate::metadata::{read_decimal, read_name, read_symbol, write_metadata};
 #[cfg(test)]
 use crate::storage_types::{AllowanceDataKey, AllowanceValue, DataKey};
-use crate::storage_types::{INSTANCE_BUMP_AMOUNT, INSTANCE_LIFETIME_THRESHOLD};
+use crate::storage_types::{
+    CheckpointSeries, Emission, INSTANCE_BUMP_AMOUNT, INSTANCE_LIFETIME_THRESHOLD,
+};
 use soroban_sdk::token::{self, Interface as _};
-use soroban_sdk::{contract, contractimpl, Address, Env, String};
+use soroban_sdk::{contract, contractimpl, symbol_short, Address, Env, String};
 use soroban_token_sdk::metadata::TokenMetadata;
 use soroban_token_sdk::TokenUtils;
 
@@ -23,7 +37,18 @@ pub struct Token;
 
 #[contractimpl]
 impl Token {
-    pub fn __constructor(e: Env, admin: Address) {
+    /// Deploys OPUS with a hard `cap` on supply. `allocation` is minted to
+    /// `holder` up front; everything after that is limited by the emission
+    /// schedule.
+    pub fn __constructor(
+        e: Env,
+        admin: Address,
+        holder: Address,
+        allocation: i128,
+        cap: i128,
+        epoch_length: u32,
+        epoch_emission: i128,
+    ) {
 
         let decimal: u32 = 7_u32;
         let name: String = String::from_str(&e, "META OPUS TOKEN TESTNET");
@@ -32,6 +57,18 @@ impl Token {
         if decimal > 18 {
             panic!("Decimal must not be greater than 18");
         }
+
+        check_nonnegative_amount(allocation);
+        check_nonnegative_amount(epoch_emission);
+
+        if cap <= 0 {
+            panic!("cap must be positive");
+        }
+
+        if epoch_length == 0 {
+            panic!("epoch length must be positive");
+        }
+
         write_administrator(&e, &admin);
         write_metadata(
             &e,
@@ -40,7 +77,23 @@ impl Token {
                 name,
                 symbol,
             },
-        )
+        );
+        write_emission(
+            &e,
+            &Emission {
+                cap,
+                start: e.ledger().sequence(),
+                epoch_length,
+                epoch_emission,
+            },
+        );
+
+        if allocation > 0 {
+            spend_cap(&e, allocation);
+            receive_balance(&e, holder.clone(), allocation);
+            increase_supply(&e, allocation);
+            TokenUtils::new(&e).events().mint(admin, holder, allocation);
+        }
     }
 
     pub fn mint(e: Env, to: Address, amount: i128) {
@@ -52,11 +105,93 @@ impl Token {
             .instance()
             .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
 
+        spend_emission(&e, amount);
         receive_balance(&e, to.clone(), amount);
+        increase_supply(&e, amount);
         TokenUtils::new(&e).events().mint(admin, to, amount);
     }
 
+    pub fn emission(e: Env) -> Emission {
+        read_emission(&e)
+    }
+
+    pub fn current_epoch(e: Env) -> u32 {
+        current_epoch(&e)
+    }
+
+    /// How much can still be minted in the current epoch.
+    pub fn remaining_emission(e: Env) -> i128 {
+        remaining_emission(&e)
+    }
+
+    pub fn total_supply(e: Env) -> i128 {
+        read_total_supply(&e)
+    }
+
+    /// Everything minted so far, including OPUS that has since been burned.
+    pub fn total_minted(e: Env) -> i128 {
+        read_total_minted(&e)
+    }
+
+    /// Balance of `id` at the close of a past `ledger`. Fails if a
+    /// checkpoint the lookup needs has been archived; restore it and retry.
+    pub fn balance_at(e: Env, id: Address, ledger: u32) -> i128 {
+        read_checkpoint_at(&e, CheckpointSeries::Balance(id), ledger)
+    }
+
+    /// Total supply at the close of a past `ledger`.
+    pub fn total_supply_at(e: Env, ledger: u32) -> i128 {
+        read_checkpoint_at(&e, CheckpointSeries::Supply, ledger)
+    }
+
+    /// Hands the voting power of `delegator`'s balance to `delegatee`.
+    /// Delegating to oneself takes it back.
+    pub fn delegate(e: Env, delegator: Address, delegatee: Address) {
+        delegator.require_auth();
+
+        e.storage()
+            .instance()
+            .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+
+        let previous = read_delegate(&e, &delegator);
+        if previous == delegatee {
+            return;
+        }
+
+        let balance = read_balance(&e, delegator.clone());
+        add_votes(&e, &previous, -balance);
+        add_votes(&e, &delegatee, balance);
+        write_delegate(&e, &delegator, &delegatee);
+        e.events().publish(
+            (symbol_short!("delegate"), delegator),
+            (previous, delegatee),
+        );
+    }
+
+    pub fn delegates(e: Env, account: Address) -> Address {
+        read_delegate(&e, &account)
+    }
+
+    /// Voting power of `account`: the balances of everyone delegating to it,
+    /// including its own unless it delegated elsewhere.
+    pub fn votes_of(e: Env, account: Address) -> i128 {
+        read_votes(&e, &account)
+    }
+
+    /// Voting power of `account` at the close of a past `ledger`.
+    pub fn votes_at(e: Env, account: Address, ledger: u32) -> i128 {
+        read_checkpoint_at(&e, CheckpointSeries::Votes(account), ledger)
+    }
+
+    /// Kept for existing callers. Only proposes `new_admin`, who still has to
+    /// call `accept_admin`, so a mistyped address cannot take over the token.
     pub fn set_admin(e: Env, new_admin: Address) {
+        Self::propose_admin(e, new_admin);
+    }
+
+    /// First step of an admin transfer. The new admin only takes over once
+    /// they call `accept_admin`.
+    pub fn propose_admin(e: Env, new_admin: Address) {
         let admin = read_administrator(&e);
         admin.require_auth();
 
@@ -64,10 +199,39 @@ impl Token {
             .instance()
             .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
 
+        write_pending_administrator(&e, &new_admin);
+        e.events()
+            .publish((symbol_short!("admin"), symbol_short!("propose")), (admin, new_admin));
+    }
+
+    pub fn accept_admin(e: Env) {
+        let new_admin = read_pending_administrator(&e).expect("no pending admin");
+        new_admin.require_auth();
+
+        e.storage()
+            .instance()
+            .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+
+        let admin = read_administrator(&e);
         write_administrator(&e, &new_admin);
+        remove_pending_administrator(&e);
         TokenUtils::new(&e).events().set_admin(admin, new_admin);
     }
 
+    pub fn cancel_admin_proposal(e: Env) {
+        let admin = read_administrator(&e);
+        admin.require_auth();
+
+        let pending = read_pending_administrator(&e).expect("no pending admin");
+        remove_pending_administrator(&e);
+        e.events()
+            .publish((symbol_short!("admin"), symbol_short!("cancel")), (admin, pending));
+    }
+
+    pub fn pending_admin(e: Env) -> Option<Address> {
+        read_pending_administrator(&e)
+    }
+
     #[cfg(test)]
     pub fn get_allowance(e: Env, from: Address, spender: Address) -> Option<AllowanceValue> {
         let key = DataKey::Allowance(AllowanceDataKey { from, spender });
@@ -146,6 +310,7 @@ impl token::Interface for Token {
             .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
 
         spend_balance(&e, from.clone(), amount);
+        decrease_supply(&e, amount);
         TokenUtils::new(&e).events().burn(from, amount);
     }
 
@@ -160,6 +325,7 @@ impl token::Interface for Token {
 
         spend_allowance(&e, from.clone(), spender, amount);
         spend_balance(&e, from.clone(), amount);
+        decrease_supply(&e, amount);
         TokenUtils::new(&e).events().burn(from, amount)
     }
 
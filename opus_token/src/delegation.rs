@@ -0,0 +1,57 @@
+use crate::checkpoint::write_checkpoint;
+use crate::storage_types::{
+    CheckpointSeries, DataKey, BALANCE_BUMP_AMOUNT, BALANCE_LIFETIME_THRESHOLD,
+};
+use soroban_sdk::{Address, Env};
+
+/// Accounts that never delegated vote for themselves.
+pub fn read_delegate(e: &Env, account: &Address) -> Address {
+    let key = DataKey::Delegate(account.clone());
+    if let Some(delegate) = e.storage().persistent().get::<DataKey, Address>(&key) {
+        e.storage()
+            .persistent()
+            .extend_ttl(&key, BALANCE_LIFETIME_THRESHOLD, BALANCE_BUMP_AMOUNT);
+        delegate
+    } else {
+        account.clone()
+    }
+}
+
+pub fn write_delegate(e: &Env, account: &Address, delegate: &Address) {
+    let key = DataKey::Delegate(account.clone());
+    if account == delegate {
+        e.storage().persistent().remove(&key);
+    } else {
+        e.storage().persistent().set(&key, delegate);
+        e.storage()
+            .persistent()
+            .extend_ttl(&key, BALANCE_LIFETIME_THRESHOLD, BALANCE_BUMP_AMOUNT);
+    }
+}
+
+pub fn read_votes(e: &Env, account: &Address) -> i128 {
+    let key = DataKey::Votes(account.clone());
+    if let Some(votes) = e.storage().persistent().get::<DataKey, i128>(&key) {
+        e.storage()
+            .persistent()
+            .extend_ttl(&key, BALANCE_LIFETIME_THRESHOLD, BALANCE_BUMP_AMOUNT);
+        votes
+    } else {
+        0
+    }
+}
+
+/// Adds `amount`, which may be negative, to the votes of `delegate`.
+pub fn add_votes(e: &Env, delegate: &Address, amount: i128) {
+    if amount == 0 {
+        return;
+    }
+
+    let votes = read_votes(e, delegate) + amount;
+    let key = DataKey::Votes(delegate.clone());
+    e.storage().persistent().set(&key, &votes);
+    e.storage()
+        .persistent()
+        .extend_ttl(&key, BALANCE_LIFETIME_THRESHOLD, BALANCE_BUMP_AMOUNT);
+    write_checkpoint(e, CheckpointSeries::Votes(delegate.clone()), votes);
+}
@@ -0,0 +1,83 @@
+use crate::balance::read_total_supply;
+use crate::storage_types::{DataKey, Emission, BALANCE_BUMP_AMOUNT, BALANCE_LIFETIME_THRESHOLD};
+use soroban_sdk::Env;
+
+pub fn read_emission(e: &Env) -> Emission {
+    let key = DataKey::Emission;
+    e.storage().instance().get(&key).unwrap()
+}
+
+pub fn write_emission(e: &Env, emission: &Emission) {
+    let key = DataKey::Emission;
+    e.storage().instance().set(&key, emission);
+}
+
+pub fn current_epoch(e: &Env) -> u32 {
+    let emission = read_emission(e);
+    (e.ledger().sequence() - emission.start) / emission.epoch_length
+}
+
+/// Amount that may be minted during `epoch`. The budget halves every epoch.
+pub fn epoch_budget(emission: &Emission, epoch: u32) -> i128 {
+    if epoch >= 127 {
+        0
+    } else {
+        emission.epoch_emission >> epoch
+    }
+}
+
+/// Everything ever minted, burned or not. Tokens deployed before this was
+/// tracked start counting from their supply at the time.
+pub fn read_total_minted(e: &Env) -> i128 {
+    e.storage()
+        .instance()
+        .get(&DataKey::TotalMinted)
+        .unwrap_or_else(|| read_total_supply(e))
+}
+
+fn read_epoch_minted(e: &Env, epoch: u32) -> i128 {
+    let key = DataKey::EpochMinted(epoch);
+    e.storage().persistent().get(&key).unwrap_or(0)
+}
+
+/// What can still be minted this epoch without breaking the epoch budget or
+/// the supply cap. Burned OPUS does not free up room under the cap.
+pub fn remaining_emission(e: &Env) -> i128 {
+    let emission = read_emission(e);
+    let epoch = current_epoch(e);
+    let budget = epoch_budget(&emission, epoch) - read_epoch_minted(e, epoch);
+    let headroom = emission.cap - read_total_minted(e);
+
+    budget.min(headroom).max(0)
+}
+
+/// Counts `amount` against the supply cap.
+pub fn spend_cap(e: &Env, amount: i128) {
+    let minted = read_total_minted(e)
+        .checked_add(amount)
+        .expect("supply overflow");
+
+    if minted > read_emission(e).cap {
+        panic!("mint exceeds supply cap");
+    }
+
+    e.storage().instance().set(&DataKey::TotalMinted, &minted);
+}
+
+pub fn spend_emission(e: &Env, amount: i128) {
+    spend_cap(e, amount);
+
+    let emission = read_emission(e);
+    let epoch = current_epoch(e);
+    let minted = read_epoch_minted(e, epoch) + amount;
+
+    if minted > epoch_budget(&emission, epoch) {
+        panic!("mint exceeds epoch emission");
+    }
+
+    let key = DataKey::EpochMinted(epoch);
+    e.storage().persistent().set(&key, &minted);
+    e.storage()
+        .persistent()
+        .extend_ttl(&key, BALANCE_LIFETIME_THRESHOLD, BALANCE_BUMP_AMOUNT);
+}
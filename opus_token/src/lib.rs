@@ -3,6 +3,9 @@
 mod admin;
 mod allowance;
 mod balance;
+mod checkpoint;
+mod delegation;
+mod emission;
 mod contract;
 mod metadata;
 mod storage_types;
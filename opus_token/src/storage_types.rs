@@ -20,6 +20,33 @@ pub struct AllowanceValue {
     pub expiration_ledger: u32,
 }
 
+/// Supply cap and reward emission. `epoch_emission` can be minted during the
+/// first `epoch_length` ledgers after `start`, half of that in the next epoch,
+/// and so on.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Emission {
+    pub cap: i128,
+    pub start: u32,
+    pub epoch_length: u32,
+    pub epoch_emission: i128,
+}
+
+/// A value recorded at the ledger it changed in.
+#[contracttype]
+pub struct Checkpoint {
+    pub ledger: u32,
+    pub amount: i128,
+}
+
+#[derive(Clone)]
+#[contracttype]
+pub enum CheckpointSeries {
+    Balance(Address),
+    Supply,
+    Votes(Address),
+}
+
 #[derive(Clone)]
 #[contracttype]
 pub enum DataKey {
@@ -27,4 +54,13 @@ pub enum DataKey {
     Balance(Address),
     State(Address),
     Admin,
+    PendingAdmin,
+    TotalSupply,
+    TotalMinted,
+    Emission,
+    EpochMinted(u32),
+    Checkpoint(CheckpointSeries, u32),
+    CheckpointCount(CheckpointSeries),
+    Delegate(Address),
+    Votes(Address),
 }
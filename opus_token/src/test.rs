@@ -1,19 +1,20 @@
 #![cfg(test)]
 extern crate std;
 
-use crate::{contract::Token, TokenClient};
+use crate::{
+    checkpoint::read_checkpoint_count, contract::Token, storage_types::CheckpointSeries,
+    TokenClient,
+};
 use soroban_sdk::{
     symbol_short,
-    testutils::{Address as _, AuthorizedFunction, AuthorizedInvocation},
-    Address, Env, IntoVal, Symbol,
+    testutils::{Address as _, AuthorizedFunction, AuthorizedInvocation, Events, Ledger},
+    vec, Address, Env, IntoVal, Symbol,
 };
 
 fn create_token<'a>(e: &Env, admin: &Address) -> TokenClient<'a> {
     let token_contract = e.register(
         Token,
-        (
-            admin,
-        ),
+        (admin, admin, 0_i128, 1_000_000_i128, 1_000_u32, 100_000_i128),
     );
     TokenClient::new(e, &token_contract)
 }
@@ -141,6 +142,52 @@ fn test() {
     assert_eq!(token.allowance(&user2, &user3), 0);
 }
 
+#[test]
+fn test_admin_transfer() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin1 = Address::generate(&e);
+    let admin2 = Address::generate(&e);
+    let admin3 = Address::generate(&e);
+    let user = Address::generate(&e);
+    let token = create_token(&e, &admin1);
+
+    token.propose_admin(&admin3);
+    token.cancel_admin_proposal();
+    assert_eq!(token.pending_admin(), None);
+    assert!(token.try_accept_admin().is_err());
+
+    // set_admin no longer hands over control on its own
+    token.set_admin(&admin3);
+    assert_eq!(token.pending_admin(), Some(admin3.clone()));
+    token.mint(&user, &1);
+    assert_eq!(e.auths()[0].0, admin1);
+
+    token.propose_admin(&admin2);
+    assert_eq!(token.pending_admin(), Some(admin2.clone()));
+
+    token.accept_admin();
+    assert_eq!(
+        e.auths(),
+        std::vec![(
+            admin2.clone(),
+            AuthorizedInvocation {
+                function: AuthorizedFunction::Contract((
+                    token.address.clone(),
+                    Symbol::new(&e, "accept_admin"),
+                    ().into_val(&e),
+                )),
+                sub_invocations: std::vec![]
+            }
+        )]
+    );
+    assert_eq!(token.pending_admin(), None);
+
+    token.mint(&user, &100);
+    assert_eq!(e.auths()[0].0, admin2);
+}
+
 #[test]
 fn test_burn() {
     let e = Env::default();
@@ -249,3 +296,268 @@ fn test_zero_allowance() {
     token.transfer_from(&spender, &from, &spender, &0);
     assert!(token.get_allowance(&from, &spender).is_none());
 }
+
+#[test]
+fn test_balance_checkpoints() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user1 = Address::generate(&e);
+    let user2 = Address::generate(&e);
+    let token = create_token(&e, &admin);
+
+    e.ledger().with_mut(|li| li.sequence_number = 10);
+    token.mint(&user1, &1000);
+    token.transfer(&user1, &user2, &100);
+
+    e.ledger().with_mut(|li| li.sequence_number = 20);
+    token.transfer(&user1, &user2, &400);
+    token.burn(&user2, &200);
+
+    e.ledger().with_mut(|li| li.sequence_number = 30);
+    assert_eq!(token.balance_at(&user1, &9), 0);
+    assert_eq!(token.balance_at(&user1, &10), 900);
+    assert_eq!(token.balance_at(&user1, &19), 900);
+    assert_eq!(token.balance_at(&user1, &20), 500);
+    assert_eq!(token.balance_at(&user2, &15), 100);
+    assert_eq!(token.balance_at(&user2, &29), 300);
+    assert_eq!(token.total_supply_at(&10), 1000);
+    assert_eq!(token.total_supply_at(&20), 800);
+    assert_eq!(token.total_supply(), 800);
+
+    assert!(token.try_balance_at(&user1, &30).is_err());
+}
+
+#[test]
+#[should_panic(expected = "Error(Storage, InternalError)")]
+fn test_unread_checkpoints_expire() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let owner = Address::generate(&e);
+    let receiver = Address::generate(&e);
+    let token = create_token(&e, &admin);
+
+    for (ledger, amount) in [(10, 1000), (20, 100), (30, 100), (40, 100)] {
+        e.ledger().with_mut(|li| li.sequence_number = ledger);
+        if ledger == 10 {
+            token.mint(&owner, &amount);
+        } else {
+            token.transfer(&owner, &receiver, &amount);
+        }
+    }
+
+    // recent lookups only touch the newest checkpoints, so the older ones
+    // are left to expire while everything else stays alive
+    for _ in 0..6 {
+        e.ledger().with_mut(|li| li.sequence_number += 100_000);
+        assert_eq!(token.balance(&owner), 700);
+        assert_eq!(token.balance_at(&owner, &35), 800);
+    }
+
+    assert_eq!(token.balance_at(&owner, &45), 700);
+
+    // checkpoint 1 has been archived and has to be restored first
+    token.balance_at(&owner, &15);
+}
+
+#[test]
+fn test_checkpoints_follow_every_balance_change() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let owner = Address::generate(&e);
+    let spender = Address::generate(&e);
+    let receiver = Address::generate(&e);
+    let token = create_token(&e, &admin);
+
+    e.ledger().with_mut(|li| li.sequence_number = 10);
+    token.mint(&owner, &1000);
+    token.approve(&owner, &spender, &1000, &200);
+
+    e.ledger().with_mut(|li| li.sequence_number = 20);
+    token.transfer_from(&spender, &owner, &receiver, &300);
+
+    e.ledger().with_mut(|li| li.sequence_number = 30);
+    token.burn_from(&spender, &owner, &200);
+
+    // several changes in one ledger leave a single checkpoint behind
+    e.ledger().with_mut(|li| li.sequence_number = 40);
+    token.transfer(&owner, &receiver, &100);
+    token.transfer(&receiver, &owner, &50);
+    token.transfer(&owner, &owner, &50);
+
+    e.ledger().with_mut(|li| li.sequence_number = 50);
+    assert_eq!(token.balance_at(&owner, &10), 1000);
+    assert_eq!(token.balance_at(&owner, &20), 700);
+    assert_eq!(token.balance_at(&owner, &30), 500);
+    assert_eq!(token.balance_at(&owner, &40), 450);
+    assert_eq!(token.balance_at(&receiver, &19), 0);
+    assert_eq!(token.balance_at(&receiver, &20), 300);
+    assert_eq!(token.balance_at(&receiver, &49), 350);
+    assert_eq!(token.total_supply_at(&29), 1000);
+    assert_eq!(token.total_supply_at(&30), 800);
+
+    e.as_contract(&token.address, || {
+        assert_eq!(read_checkpoint_count(&e, &CheckpointSeries::Balance(owner.clone())), 4);
+        assert_eq!(read_checkpoint_count(&e, &CheckpointSeries::Balance(receiver.clone())), 2);
+        assert_eq!(read_checkpoint_count(&e, &CheckpointSeries::Supply), 2);
+    });
+}
+
+#[test]
+fn test_balance_at_before_first_checkpoint() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user = Address::generate(&e);
+    let token = create_token(&e, &admin);
+
+    e.ledger().with_mut(|li| li.sequence_number = 10);
+    assert_eq!(token.balance_at(&user, &9), 0);
+    assert_eq!(token.total_supply_at(&9), 0);
+
+    token.mint(&user, &100);
+    assert!(token.try_balance_at(&user, &10).is_err());
+    assert!(token.try_total_supply_at(&10).is_err());
+
+    e.ledger().with_mut(|li| li.sequence_number = 11);
+    assert_eq!(token.balance_at(&user, &9), 0);
+    assert_eq!(token.balance_at(&user, &10), 100);
+}
+
+#[test]
+fn test_delegation() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let artist = Address::generate(&e);
+    let curator = Address::generate(&e);
+    let user = Address::generate(&e);
+    let spender = Address::generate(&e);
+    let token = create_token(&e, &admin);
+
+    e.ledger().with_mut(|li| li.sequence_number = 10);
+    token.mint(&artist, &1000);
+    assert_eq!(token.delegates(&artist), artist);
+    assert_eq!(token.votes_of(&artist), 1000);
+
+    e.ledger().with_mut(|li| li.sequence_number = 20);
+    token.delegate(&artist, &curator);
+    assert_eq!(
+        e.events().all(),
+        vec![
+            &e,
+            (
+                token.address.clone(),
+                (symbol_short!("delegate"), artist.clone()).into_val(&e),
+                (artist.clone(), curator.clone()).into_val(&e),
+            )
+        ]
+    );
+    assert_eq!(token.delegates(&artist), curator);
+    assert_eq!(token.votes_of(&artist), 0);
+    assert_eq!(token.votes_of(&curator), 1000);
+
+    // votes follow the balance through every path
+    token.transfer(&artist, &user, &300);
+    assert_eq!(token.votes_of(&curator), 700);
+    assert_eq!(token.votes_of(&user), 300);
+
+    token.approve(&artist, &spender, &500, &200);
+    token.transfer_from(&spender, &artist, &user, &100);
+    token.burn_from(&spender, &artist, &100);
+    token.burn(&artist, &100);
+    token.mint(&artist, &50);
+    assert_eq!(token.votes_of(&curator), 450);
+    assert_eq!(token.votes_of(&user), 400);
+
+    e.ledger().with_mut(|li| li.sequence_number = 30);
+    token.delegate(&user, &curator);
+    assert_eq!(token.votes_of(&curator), 850);
+    assert_eq!(token.votes_of(&user), 0);
+    token.transfer(&user, &artist, &400);
+    assert_eq!(token.votes_of(&curator), 850);
+
+    // delegating to oneself takes the votes back
+    e.ledger().with_mut(|li| li.sequence_number = 40);
+    token.delegate(&artist, &artist);
+    assert_eq!(token.delegates(&artist), artist);
+    assert_eq!(token.votes_of(&artist), 850);
+    assert_eq!(token.votes_of(&curator), 0);
+
+    e.ledger().with_mut(|li| li.sequence_number = 50);
+    assert_eq!(token.votes_at(&artist, &19), 1000);
+    assert_eq!(token.votes_at(&curator, &20), 450);
+    assert_eq!(token.votes_at(&curator, &30), 850);
+    assert_eq!(token.votes_at(&curator, &40), 0);
+    assert_eq!(token.votes_at(&artist, &40), 850);
+}
+
+#[test]
+fn test_emission_schedule() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let holder = Address::generate(&e);
+    let user = Address::generate(&e);
+    let token = TokenClient::new(
+        &e,
+        &e.register(Token, (&admin, &holder, 500_i128, 2_000_i128, 100_u32, 800_i128)),
+    );
+
+    assert_eq!(token.balance(&holder), 500);
+    assert_eq!(token.total_supply(), 500);
+    assert_eq!(token.remaining_emission(), 800);
+
+    token.mint(&user, &600);
+    assert_eq!(token.remaining_emission(), 200);
+    assert!(token.try_mint(&user, &201).is_err());
+
+    // the budget halves every epoch
+    e.ledger().with_mut(|li| li.sequence_number = 100);
+    assert_eq!(token.current_epoch(), 1);
+    assert_eq!(token.remaining_emission(), 400);
+    token.mint(&user, &400);
+
+    // and is capped by what is left under the supply cap
+    e.ledger().with_mut(|li| li.sequence_number = 200);
+    assert_eq!(token.remaining_emission(), 200);
+    token.mint(&user, &200);
+    assert_eq!(token.total_supply(), 1_700);
+
+    e.ledger().with_mut(|li| li.sequence_number = 300);
+    assert_eq!(token.remaining_emission(), 100);
+    token.burn(&user, &500);
+    assert_eq!(token.remaining_emission(), 100);
+    assert!(token.try_mint(&user, &101).is_err());
+}
+
+#[test]
+fn test_burned_opus_cannot_be_reminted() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let holder = Address::generate(&e);
+    let token = TokenClient::new(
+        &e,
+        &e.register(Token, (&admin, &holder, 900_i128, 1_000_i128, 100_u32, 800_i128)),
+    );
+
+    assert_eq!(token.remaining_emission(), 100);
+    token.burn(&holder, &500);
+    assert_eq!(token.total_supply(), 400);
+    assert_eq!(token.total_minted(), 900);
+    assert_eq!(token.remaining_emission(), 100);
+
+    token.mint(&holder, &100);
+    assert_eq!(token.total_minted(), 1_000);
+    assert!(token.try_mint(&holder, &1).is_err());
+}
@@ -8,6 +8,7 @@ use soroban_sdk::{contract, contractimpl, symbol_short, Address, BytesN, Env, Sy
 pub struct PintheonFactory;
 
 const ADMIN: Symbol = symbol_short!("admin");
+const PENDING: Symbol = symbol_short!("pending");
 
 #[contractimpl]
 impl PintheonFactory {
@@ -16,6 +17,44 @@ impl PintheonFactory {
         env.storage().instance().set(&ADMIN, &admin);
     }
 
+    /// Proposes a new administrator, who takes over once they accept.
+    pub fn propose_admin(env: Env, new_admin: Address) {
+        let admin: Address = env.storage().instance().get(&ADMIN).unwrap();
+        admin.require_auth();
+
+        env.storage().instance().set(&PENDING, &new_admin);
+        env.events().publish((ADMIN, symbol_short!("propose")), (admin, new_admin));
+    }
+
+    /// Completes the handover. This has to be authorized by the proposed
+    /// administrator.
+    pub fn accept_admin(env: Env) {
+        let new_admin: Address = env.storage().instance().get(&PENDING).expect("no pending admin");
+        new_admin.require_auth();
+
+        let admin: Address = env.storage().instance().get(&ADMIN).unwrap();
+        env.storage().instance().set(&ADMIN, &new_admin);
+        env.storage().instance().remove(&PENDING);
+        env.events().publish((ADMIN, symbol_short!("accept")), (admin, new_admin));
+    }
+
+    pub fn cancel_admin_proposal(env: Env) {
+        let admin: Address = env.storage().instance().get(&ADMIN).unwrap();
+        admin.require_auth();
+
+        let pending: Address = env.storage().instance().get(&PENDING).expect("no pending admin");
+        env.storage().instance().remove(&PENDING);
+        env.events().publish((ADMIN, symbol_short!("cancel")), (admin, pending));
+    }
+
+    pub fn admin(env: Env) -> Address {
+        env.storage().instance().get(&ADMIN).unwrap()
+    }
+
+    pub fn pending_admin(env: Env) -> Option<Address> {
+        env.storage().instance().get(&PENDING)
+    }
+
     /// Deploys the contract on behalf of the `PintheonFactory` contract.
     ///
     /// This has to be authorized by the `PintheonFactory`s administrator.    
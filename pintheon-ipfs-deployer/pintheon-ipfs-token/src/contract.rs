@@ -1,6 +1,9 @@
 //! This contract demonstrates a sample implementation of the Soroban token
 //! interface.
-use crate::admin::{read_administrator, write_administrator};
+use crate::admin::{
+    read_administrator, read_pending_administrator, remove_pending_administrator,
+    write_administrator, write_pending_administrator,
+};
 use crate::allowance::{read_allowance, spend_allowance, write_allowance};
 use crate::balance::{read_balance, receive_balance, spend_balance};
 use crate::metadata::{FileTokenInterface, read_decimal, read_name, read_symbol, read_ipfs_hash, read_file_type, read_published, read_gateways, read_ipns_hash, write_metadata};
@@ -8,7 +11,7 @@ use crate::metadata::{FileTokenInterface, read_decimal, read_name, read_symbol,
 use crate::storage_types::{AllowanceDataKey, AllowanceValue, DataKey};
 use crate::storage_types::{INSTANCE_BUMP_AMOUNT, INSTANCE_LIFETIME_THRESHOLD};
 use soroban_sdk::token::{self, Interface as _};
-use soroban_sdk::{contract, contractimpl, Address, Env, String};
+use soroban_sdk::{contract, contractimpl, symbol_short, Address, Env, String};
 use hvym_file_token::filemetadata::FileTokenMetadata;
 use hvym_file_token::TokenUtils;
 
@@ -62,7 +65,15 @@ impl Token {
         TokenUtils::new(&e).events().mint(admin, to, amount);
     }
 
+    /// Kept for existing callers. Only proposes `new_admin`, who still has to
+    /// call `accept_admin`, so a mistyped address cannot take over the token.
     pub fn set_admin(e: Env, new_admin: Address) {
+        Self::propose_admin(e, new_admin);
+    }
+
+    /// First step of an admin transfer. The new admin only takes over once
+    /// they call `accept_admin`.
+    pub fn propose_admin(e: Env, new_admin: Address) {
         let admin = read_administrator(&e);
         admin.require_auth();
 
@@ -70,10 +81,39 @@ impl Token {
             .instance()
             .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
 
+        write_pending_administrator(&e, &new_admin);
+        e.events()
+            .publish((symbol_short!("admin"), symbol_short!("propose")), (admin, new_admin));
+    }
+
+    pub fn accept_admin(e: Env) {
+        let new_admin = read_pending_administrator(&e).expect("no pending admin");
+        new_admin.require_auth();
+
+        e.storage()
+            .instance()
+            .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+
+        let admin = read_administrator(&e);
         write_administrator(&e, &new_admin);
+        remove_pending_administrator(&e);
         TokenUtils::new(&e).events().set_admin(admin, new_admin);
     }
 
+    pub fn cancel_admin_proposal(e: Env) {
+        let admin = read_administrator(&e);
+        admin.require_auth();
+
+        let pending = read_pending_administrator(&e).expect("no pending admin");
+        remove_pending_administrator(&e);
+        e.events()
+            .publish((symbol_short!("admin"), symbol_short!("cancel")), (admin, pending));
+    }
+
+    pub fn pending_admin(e: Env) -> Option<Address> {
+        read_pending_administrator(&e)
+    }
+
     #[cfg(test)]
     pub fn get_allowance(e: Env, from: Address, spender: Address) -> Option<AllowanceValue> {
         let key = DataKey::Allowance(AllowanceDataKey { from, spender });
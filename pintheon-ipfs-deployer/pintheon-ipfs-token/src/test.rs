@@ -159,6 +159,52 @@ fn test() {
     assert_eq!(token.allowance(&user2, &user3), 0);
 }
 
+#[test]
+fn test_admin_transfer() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin1 = Address::generate(&e);
+    let admin2 = Address::generate(&e);
+    let admin3 = Address::generate(&e);
+    let user = Address::generate(&e);
+    let token = create_token(&e, &admin1);
+
+    token.propose_admin(&admin3);
+    token.cancel_admin_proposal();
+    assert_eq!(token.pending_admin(), None);
+    assert!(token.try_accept_admin().is_err());
+
+    // set_admin no longer hands over control on its own
+    token.set_admin(&admin3);
+    assert_eq!(token.pending_admin(), Some(admin3.clone()));
+    token.mint(&user, &1);
+    assert_eq!(e.auths()[0].0, admin1);
+
+    token.propose_admin(&admin2);
+    assert_eq!(token.pending_admin(), Some(admin2.clone()));
+
+    token.accept_admin();
+    assert_eq!(
+        e.auths(),
+        std::vec![(
+            admin2.clone(),
+            AuthorizedInvocation {
+                function: AuthorizedFunction::Contract((
+                    token.address.clone(),
+                    Symbol::new(&e, "accept_admin"),
+                    ().into_val(&e),
+                )),
+                sub_invocations: std::vec![]
+            }
+        )]
+    );
+    assert_eq!(token.pending_admin(), None);
+
+    token.mint(&user, &100);
+    assert_eq!(e.auths()[0].0, admin2);
+}
+
 #[test]
 fn test_burn() {
     let e = Env::default();
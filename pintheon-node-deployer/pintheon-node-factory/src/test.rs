@@ -7,7 +7,7 @@ use alloc::vec;
 use soroban_sdk::{
     symbol_short,
     testutils::{Address as _, AuthorizedFunction, AuthorizedInvocation},
-    Address, BytesN, Val, Env, FromVal, IntoVal, String, Vec,
+    Address, BytesN, Val, Env, FromVal, IntoVal, String, Symbol, Vec,
 };
 
 // The contract that will be deployed by the deployer contract.
@@ -60,3 +60,33 @@ fn test() {
     assert_eq!(token.descriptor(), descriptor);
     assert_eq!(token.established(), established);
 }
+
+#[test]
+fn test_admin_transfer() {
+    let env = Env::default();
+    let admin = Address::generate(&env);
+    let typo = Address::generate(&env);
+    let new_admin = Address::generate(&env);
+    let deployer_client = PintheonFactoryClient::new(&env, &env.register(PintheonFactory, (&admin,)));
+    env.mock_all_auths();
+
+    deployer_client.propose_admin(&typo);
+    deployer_client.cancel_admin_proposal();
+    assert_eq!(deployer_client.pending_admin(), None);
+
+    deployer_client.propose_admin(&new_admin);
+    assert_eq!(deployer_client.admin(), admin);
+
+    deployer_client.accept_admin();
+    let expected_auth = AuthorizedInvocation {
+        function: AuthorizedFunction::Contract((
+            deployer_client.address.clone(),
+            Symbol::new(&env, "accept_admin"),
+            ().into_val(&env),
+        )),
+        sub_invocations: vec![],
+    };
+    assert_eq!(env.auths(), vec![(new_admin.clone(), expected_auth)]);
+    assert_eq!(deployer_client.admin(), new_admin);
+    assert_eq!(deployer_client.pending_admin(), None);
+}